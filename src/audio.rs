@@ -0,0 +1,314 @@
+use std::time::Duration;
+
+use bevy::{audio::Volume, prelude::*, utils::HashMap};
+
+use crate::{
+    damage::BossDiedEvent,
+    health::{Died, HealthSet},
+    lives::GameOverEvent,
+    settings::Settings,
+    GameState,
+};
+
+/// Track played on the title screen, if any. There are no music assets yet.
+const TITLE_MUSIC: Option<&str> = None;
+
+const MUSIC_VOLUME: f32 = 0.6;
+
+const MUSIC_FADE_SECS: f32 = 1.5;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SoundEvent>()
+            .add_event::<MusicEvent>()
+            .add_systems(Startup, startup)
+            .add_systems(PostUpdate, death_sound.in_set(HealthSet::Death))
+            .add_systems(PostUpdate, play_sounds.after(HealthSet::Death))
+            .add_systems(
+                OnEnter(GameState::Title),
+                |mut music: EventWriter<MusicEvent>| {
+                    if let Some(path) = TITLE_MUSIC {
+                        music.send(MusicEvent::Play(path.to_string()));
+                    }
+                },
+            )
+            .add_systems(
+                Update,
+                (|mut music: EventWriter<MusicEvent>| {
+                    music.send(MusicEvent::FadeOut);
+                })
+                .run_if(on_event::<GameOverEvent>().or_else(on_event::<BossDiedEvent>())),
+            )
+            .add_systems(PostUpdate, (music_events, fade_music).chain());
+    }
+}
+
+fn startup(mut commands: Commands) {
+    commands.init_resource::<SoundLibrary>();
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Sound {
+    Hit,
+    EnemyShot,
+    PlayerShot,
+    PlayerDie,
+    MenuMove,
+    MenuSelect,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundCategory {
+    Sfx,
+    Ui,
+    Music,
+}
+
+impl SoundCategory {
+    /// Volume setting of the category, from 0 to 1.
+    fn level(self, settings: &Settings) -> f32 {
+        match self {
+            SoundCategory::Sfx | SoundCategory::Ui => settings.sfx_volume,
+            SoundCategory::Music => settings.music_volume,
+        }
+    }
+}
+
+struct SoundDef {
+    path: &'static str,
+    category: SoundCategory,
+    volume: f32,
+    /// Minimum time between two plays of the sound.
+    cooldown: Duration,
+    /// Maximum number of instances of the sound playing at once.
+    max_voices: usize,
+}
+
+impl Sound {
+    const ALL: [Sound; 6] = [
+        Sound::Hit,
+        Sound::EnemyShot,
+        Sound::PlayerShot,
+        Sound::PlayerDie,
+        Sound::MenuMove,
+        Sound::MenuSelect,
+    ];
+
+    fn def(self) -> SoundDef {
+        match self {
+            Sound::Hit => SoundDef {
+                path: "sounds/explosion.ogg",
+                category: SoundCategory::Sfx,
+                volume: 0.5,
+                cooldown: Duration::from_millis(50),
+                max_voices: 4,
+            },
+            Sound::EnemyShot => SoundDef {
+                path: "sounds/ice.ogg",
+                category: SoundCategory::Sfx,
+                volume: 0.2,
+                cooldown: Duration::from_millis(40),
+                max_voices: 6,
+            },
+            Sound::PlayerShot => SoundDef {
+                path: "sounds/splash_03.ogg",
+                category: SoundCategory::Sfx,
+                volume: 1.0,
+                cooldown: Duration::from_millis(50),
+                max_voices: 2,
+            },
+            Sound::PlayerDie => SoundDef {
+                path: "sounds/hit_01.ogg",
+                category: SoundCategory::Sfx,
+                volume: 1.0,
+                cooldown: Duration::ZERO,
+                max_voices: 1,
+            },
+            Sound::MenuMove => SoundDef {
+                path: "sounds/ice.ogg",
+                category: SoundCategory::Ui,
+                volume: 0.3,
+                cooldown: Duration::from_millis(30),
+                max_voices: 2,
+            },
+            Sound::MenuSelect => SoundDef {
+                path: "sounds/splash_03.ogg",
+                category: SoundCategory::Ui,
+                volume: 0.6,
+                cooldown: Duration::from_millis(100),
+                max_voices: 1,
+            },
+        }
+    }
+}
+
+/// Requests playing a sound. Requests over the sound's cooldown or voice limit are dropped.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SoundEvent(pub Sound);
+
+/// Plays the sound when the entity dies.
+#[derive(Component)]
+pub struct DeathSound(pub Sound);
+
+fn death_sound(
+    mut died_events: EventReader<Died>,
+    q: Query<&DeathSound>,
+    mut sounds: EventWriter<SoundEvent>,
+) {
+    for ev in died_events.read() {
+        if let Ok(DeathSound(sound)) = q.get(ev.entity) {
+            sounds.send(SoundEvent(*sound));
+        }
+    }
+}
+
+/// A playing sound. Despawned by bevy_audio when the playback finishes.
+#[derive(Component)]
+struct Voice(Sound);
+
+#[derive(Resource)]
+struct SoundLibrary {
+    handles: HashMap<Sound, Handle<AudioSource>>,
+}
+
+impl FromWorld for SoundLibrary {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        let handles = Sound::ALL
+            .into_iter()
+            .map(|sound| (sound, asset_server.load(sound.def().path)))
+            .collect();
+        Self { handles }
+    }
+}
+
+fn play_sounds(
+    mut commands: Commands,
+    mut events: EventReader<SoundEvent>,
+    voices: Query<&Voice>,
+    library: Res<SoundLibrary>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+    mut last_played: Local<HashMap<Sound, Duration>>,
+) {
+    if events.is_empty() {
+        return;
+    }
+
+    let mut playing = HashMap::<Sound, usize>::default();
+    for Voice(sound) in &voices {
+        *playing.entry(*sound).or_default() += 1;
+    }
+
+    let now = time.elapsed();
+    for &SoundEvent(sound) in events.read() {
+        let def = sound.def();
+
+        let voices = playing.entry(sound).or_default();
+        if *voices >= def.max_voices {
+            continue;
+        }
+        if let Some(&last) = last_played.get(&sound) {
+            if now < last + def.cooldown {
+                continue;
+            }
+        }
+        *voices += 1;
+        last_played.insert(sound, now);
+
+        let volume = Volume::new_relative(def.volume * def.category.level(&settings));
+        commands.spawn((
+            Name::new("Sound"),
+            Voice(sound),
+            AudioBundle {
+                source: library.handles[&sound].clone(),
+                settings: PlaybackSettings::DESPAWN.with_volume(volume),
+            },
+        ));
+    }
+}
+
+/// Controls the background music. Changing the track crossfades from the current one.
+#[derive(Event, Clone, Debug)]
+pub enum MusicEvent {
+    Play(String),
+    FadeOut,
+}
+
+#[derive(Component)]
+struct MusicTrack {
+    path: String,
+    /// Fade level from 0 to 1.
+    gain: f32,
+    fading_out: bool,
+}
+
+fn music_events(
+    mut commands: Commands,
+    mut events: EventReader<MusicEvent>,
+    mut tracks: Query<&mut MusicTrack>,
+    asset_server: Res<AssetServer>,
+) {
+    for ev in events.read() {
+        match ev {
+            MusicEvent::Play(path) => {
+                if tracks.iter().any(|t| !t.fading_out && t.path == *path) {
+                    continue;
+                }
+                for mut track in &mut tracks {
+                    track.fading_out = true;
+                }
+                commands.spawn((
+                    Name::new("Music"),
+                    MusicTrack {
+                        path: path.clone(),
+                        gain: 0.,
+                        fading_out: false,
+                    },
+                    AudioBundle {
+                        source: asset_server.load(path.clone()),
+                        settings: PlaybackSettings::LOOP.with_volume(Volume::new_relative(0.)),
+                    },
+                ));
+            }
+            MusicEvent::FadeOut => {
+                for mut track in &mut tracks {
+                    track.fading_out = true;
+                }
+            }
+        }
+    }
+}
+
+fn fade_music(
+    mut commands: Commands,
+    mut tracks: Query<(Entity, &mut MusicTrack, Option<&AudioSink>)>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+) {
+    let step = time.delta_seconds() / MUSIC_FADE_SECS;
+
+    for (id, mut track, sink) in &mut tracks {
+        if track.fading_out {
+            track.gain -= step;
+            if track.gain <= 0. {
+                commands.entity(id).despawn();
+                continue;
+            }
+        } else {
+            track.gain = (track.gain + step).min(1.);
+        }
+
+        // The sink is added once the track starts playing.
+        if let Some(sink) = sink {
+            sink.set_volume(
+                track.gain
+                    * MUSIC_VOLUME
+                    * settings.master_volume
+                    * SoundCategory::Music.level(&settings),
+            );
+        }
+    }
+}
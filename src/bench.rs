@@ -0,0 +1,131 @@
+use bevy::{app::AppExit, prelude::*};
+use rand::Rng;
+
+use crate::{
+    bullet_pool::{BulletPool, PooledBullet},
+    enemy::{spawn_enemy_bullet, EnemyBullet, EnemyResource},
+    lives::Invulnerable,
+    player::Player,
+    title::{GameMode, TitleRoot},
+    GameState, MainCamera, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+/// Command line flag running the bullet benchmark instead of the game.
+pub const BENCH_FLAG: &str = "--bench-bullets";
+
+/// Live bullets kept on screen during the benchmark.
+pub const BENCH_BULLETS: usize = 5_000;
+
+/// Frames skipped after reaching [`BENCH_BULLETS`] before measuring.
+const WARMUP_FRAMES: u32 = 120;
+
+const MEASURED_FRAMES: usize = 1_000;
+
+/// Starts a practice run with an invulnerable player, keeps [`BENCH_BULLETS`] pooled bullets on
+/// screen and prints the mean and 95th percentile frame time before exiting.
+pub struct BulletBenchPlugin;
+
+impl Plugin for BulletBenchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameTimes>()
+            .add_systems(
+                Update,
+                (|mut commands: Commands,
+                  mut state: ResMut<NextState<GameState>>,
+                  title_root: Query<Entity, With<TitleRoot>>| {
+                    commands.insert_resource(GameMode::Practice);
+                    state.set(GameState::Main);
+                    for root in &title_root {
+                        commands.entity(root).despawn_recursive();
+                    }
+                })
+                .run_if(in_state(GameState::Title)),
+            )
+            .add_systems(
+                Update,
+                (
+                    |mut commands: Commands, players: Query<Entity, Added<Player>>| {
+                        for id in &players {
+                            commands.entity(id).insert(Invulnerable::forever());
+                        }
+                    },
+                    top_up_bullets,
+                    record_frame_time,
+                )
+                    .run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct FrameTimes {
+    warmup: u32,
+    /// Frame times in seconds.
+    samples: Vec<f32>,
+}
+
+/// Fills the view with still enemy bullets taken from the pool.
+pub fn scatter_bullets(
+    commands: &mut Commands,
+    pool: &mut BulletPool,
+    res: &EnemyResource,
+    center: Vec2,
+    count: usize,
+) {
+    let half_screen = Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.;
+    let mut rng = rand::thread_rng();
+    for _ in 0..count {
+        let offset = Vec2::new(rng.gen::<f32>() * 2. - 1., rng.gen::<f32>() * 2. - 1.);
+        let pos = center + offset * half_screen;
+        spawn_enemy_bullet(commands, pool, res, pos.extend(5.), Vec3::ZERO, None);
+    }
+}
+
+fn live_bullets(bullets: &Query<&PooledBullet, With<EnemyBullet>>) -> usize {
+    bullets.iter().filter(|pooled| pooled.is_active()).count()
+}
+
+// Bullets scrolling out of the view are culled, replace them to keep the count steady.
+fn top_up_bullets(
+    mut commands: Commands,
+    mut pool: ResMut<BulletPool>,
+    res: Res<EnemyResource>,
+    bullets: Query<&PooledBullet, With<EnemyBullet>>,
+    camera: Query<&Transform, With<MainCamera>>,
+) {
+    let missing = BENCH_BULLETS.saturating_sub(live_bullets(&bullets));
+    if missing > 0 {
+        let center = camera.single().translation.xy();
+        scatter_bullets(&mut commands, &mut pool, &res, center, missing);
+    }
+}
+
+fn record_frame_time(
+    mut times: ResMut<FrameTimes>,
+    bullets: Query<&PooledBullet, With<EnemyBullet>>,
+    time: Res<Time<Real>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if times.warmup < WARMUP_FRAMES {
+        if live_bullets(&bullets) >= BENCH_BULLETS {
+            times.warmup += 1;
+        }
+        return;
+    }
+
+    times.samples.push(time.delta_seconds());
+    if times.samples.len() < MEASURED_FRAMES {
+        return;
+    }
+
+    let mut samples = std::mem::take(&mut times.samples);
+    samples.sort_by(f32::total_cmp);
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    let p95 = samples[samples.len() * 95 / 100];
+    info!(
+        "{BENCH_BULLETS} bullets over {MEASURED_FRAMES} frames: mean {:.2} ms, p95 {:.2} ms",
+        mean * 1000.,
+        p95 * 1000.
+    );
+    exit.send(AppExit);
+}
@@ -1,6 +1,7 @@
 use std::time::Duration;
 
-use bevy::{prelude::*, sprite::Mesh2dHandle, audio::{Volume, VolumeLevel}};
+use bevy::{prelude::*, sprite::Mesh2dHandle};
+#[cfg(feature = "dev")]
 use bevy_debug_text_overlay::screen_print;
 use bevy_tweening::{
     lens::{TransformPositionLens, TransformRotationLens},
@@ -16,11 +17,21 @@ use seldom_state::{
 };
 
 use crate::{
-    enemy::{EnemyBullet, EnemyResource, StraightBullet},
-    health::Health,
-    item::Item,
+    audio::{Sound, SoundEvent},
+    bullet_collision::{BulletCollider, Hurtbox, HurtboxShape, Team},
+    bullet_pool::{BulletKind, BulletPool},
+    culling::Cull,
+    damage::{Damage, Resistances, WeakPoint, ENEMY_BULLET_DAMAGE},
+    difficulty::Difficulty,
+    enemy::{EnemyBullet, StraightBullet},
+    health::{DamageKind, DespawnOnDeath, Health, HealthBarStyle, HealthChanged, HealthSet},
+    item::{DropItems, Item},
     level::ScrollDoneEvent,
-    MyLayer, SCREEN_HEIGHT, SCREEN_WIDTH,
+    particle::{ExplodeOnDeath, ParticleEmitter},
+    pause::PauseState,
+    score::{ScoreValue, BOSS_SCORE},
+    state_scoped::StateScoped,
+    GameState, MyLayer, SCREEN_HEIGHT, SCREEN_WIDTH,
 };
 
 pub const BOSS_SIZE: f32 = 100.0;
@@ -29,21 +40,59 @@ pub const BOSS_PADDING: f32 = 25.0;
 
 pub const BOSS_BULLET_SIZE: f32 = 6.;
 
+/// Health fractions at which the boss fight enters its next phase.
+pub const BOSS_PHASES: [f32; 2] = [2. / 3., 1. / 3.];
+
+/// Sent when the boss health crosses one of [`BOSS_PHASES`], with the index of the new phase.
+#[derive(Event)]
+pub struct BossPhaseChanged(pub usize);
+
+fn boss_phase_system(
+    mut health_events: EventReader<HealthChanged>,
+    bosses: Query<&Health, With<Boss>>,
+    mut phase_events: EventWriter<BossPhaseChanged>,
+) {
+    for ev in health_events.read() {
+        let Ok(health) = bosses.get(ev.entity) else {
+            continue;
+        };
+        let phase = |h: f32| {
+            BOSS_PHASES
+                .iter()
+                .filter(|&&p| h / health.max_health <= p)
+                .count()
+        };
+        let (old, new) = (phase(ev.old), phase(ev.new));
+        if new > old && ev.new > 0. {
+            phase_events.send(BossPhaseChanged(new));
+        }
+    }
+}
+
 pub struct BossPlugin;
 
 impl Plugin for BossPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<ScrollDoneEvent>();
+        app.add_event::<ScrollDoneEvent>()
+            .add_event::<BossPhaseChanged>()
+            .add_systems(PostUpdate, boss_phase_system.after(HealthSet::Damage));
         app.add_plugins(StateMachinePlugin);
         app.add_plugins(TweeningPlugin);
 
         app.add_systems(Startup, startup);
         app.add_systems(PostUpdate, spawn_boss);
-        app.add_systems(Update, idle_system);
-        app.add_systems(Update, attack_bottom_start);
-        app.add_systems(Update, attack_top_start);
-        app.add_systems(Update, attack_system);
-        app.add_systems(Update, attack_end);
+        // The state machine only advances through these systems, so gating them freezes the boss.
+        app.add_systems(
+            Update,
+            (
+                idle_system,
+                attack_bottom_start,
+                attack_top_start,
+                attack_system,
+                attack_end,
+            )
+                .run_if(in_state(PauseState::Running)),
+        );
         app.add_systems(
             Update,
             (
@@ -51,7 +100,8 @@ impl Plugin for BossPlugin {
                 moving_to_bottom_start,
                 rotating_start,
                 tween_end,
-            ),
+            )
+                .run_if(in_state(PauseState::Running)),
         );
         // app.add_systems(
         //     Update,
@@ -85,6 +135,11 @@ fn spawn_boss(
                     health: 200.,
                     max_health: 200.,
                 },
+                HealthBarStyle {
+                    width: BOSS_SIZE * 1.2,
+                    segments: BOSS_PHASES.len() as u32 + 1,
+                    ..default()
+                },
             ))
             .insert((
                 Idle,
@@ -98,8 +153,30 @@ fn spawn_boss(
             ))
             .insert((
                 Collider::cuboid(BOSS_SIZE, BOSS_SIZE),
-                CollisionLayers::new([MyLayer::Enemy], [MyLayer::PlayerBullet]),
+                CollisionLayers::new([MyLayer::Enemy], [MyLayer::Player]),
                 RigidBody::Kinematic,
+                Hurtbox {
+                    shape: HurtboxShape::Rect(Vec2::splat(BOSS_SIZE / 2.)),
+                    team: Team::Enemy,
+                },
+            ))
+            .insert((
+                DropItems {
+                    count: 32,
+                    spread: 50.,
+                },
+                ScoreValue(BOSS_SCORE),
+                DespawnOnDeath,
+                ExplodeOnDeath(ParticleEmitter::boss_explosion()),
+            ))
+            .insert((
+                Resistances::default().with(DamageKind::Charged, 2.0),
+                // The umbrella's handle
+                WeakPoint {
+                    offset: Vec2::new(0., -BOSS_SIZE / 4.),
+                    radius: BOSS_SIZE / 4.,
+                    multiplier: 1.5,
+                },
             ))
             .with_children(|parent| {
                 parent.spawn(SpriteBundle {
@@ -157,6 +234,26 @@ struct MovingToBottom;
 #[derive(Clone, Component, Reflect)]
 struct Rotating;
 
+/// Name of the state the boss is in, for debugging.
+#[cfg(feature = "dev")]
+pub fn state_name(boss: EntityRef) -> &'static str {
+    if boss.contains::<Idle>() {
+        "Idle"
+    } else if boss.contains::<AttackBottom>() {
+        "AttackBottom"
+    } else if boss.contains::<MovingToTop>() {
+        "MovingToTop"
+    } else if boss.contains::<AttackTop>() {
+        "AttackTop"
+    } else if boss.contains::<MovingToBottom>() {
+        "MovingToBottom"
+    } else if boss.contains::<Rotating>() {
+        "Rotating"
+    } else {
+        "None"
+    }
+}
+
 fn idle_system(
     mut commands: Commands,
     boss: Query<Entity, With<Boss>>,
@@ -173,7 +270,7 @@ fn idle_system(
 }
 
 #[derive(Component)]
-struct BossBullet;
+pub struct BossBullet;
 
 const ATTACK_NUM: u32 = 16;
 
@@ -182,9 +279,14 @@ struct AttackState {
     direction: Vec2,
     timer: Timer,
     num: u32,
+    total: u32,
 }
 
-fn attack_bottom_start(mut commands: Commands, boss: Query<Entity, Added<AttackBottom>>) {
+fn attack_bottom_start(
+    mut commands: Commands,
+    boss: Query<Entity, Added<AttackBottom>>,
+    difficulty: Res<Difficulty>,
+) {
     let Ok(id) = boss.get_single() else {
         return;
     };
@@ -194,11 +296,16 @@ fn attack_bottom_start(mut commands: Commands, boss: Query<Entity, Added<AttackB
     commands.entity(id).insert(AttackState {
         timer: Timer::new(Duration::from_millis(100), TimerMode::Repeating),
         num: 0,
+        total: difficulty.bullet_count(ATTACK_NUM),
         direction: Vec2::Y,
     });
 }
 
-fn attack_top_start(mut commands: Commands, boss: Query<Entity, Added<AttackTop>>) {
+fn attack_top_start(
+    mut commands: Commands,
+    boss: Query<Entity, Added<AttackTop>>,
+    difficulty: Res<Difficulty>,
+) {
     let Ok(id) = boss.get_single() else {
         return;
     };
@@ -208,32 +315,35 @@ fn attack_top_start(mut commands: Commands, boss: Query<Entity, Added<AttackTop>
     commands.entity(id).insert(AttackState {
         timer: Timer::new(Duration::from_millis(100), TimerMode::Repeating),
         num: 0,
+        total: difficulty.bullet_count(ATTACK_NUM),
         direction: -Vec2::Y,
     });
 }
 
 fn attack_system(
     mut commands: Commands,
+    mut pool: ResMut<BulletPool>,
     mut boss: Query<(&Transform, &mut AttackState)>,
     time: Res<Time<Virtual>>,
     res: Res<BossResource>,
-    enemy_res: Res<EnemyResource>,
+    mut sounds: EventWriter<SoundEvent>,
 ) {
     for (transform, mut state) in &mut boss {
-        if state.num < ATTACK_NUM && state.timer.tick(time.elapsed()).just_finished() {
+        if state.num < state.total && state.timer.tick(time.elapsed()).just_finished() {
             let num = state.num;
 
             let center_angle = -state.direction.angle_between(Vec2::X);
             let start_angle = center_angle + std::f32::consts::PI / 2.;
 
-            let angle = start_angle - std::f32::consts::PI / ATTACK_NUM as f32 * num as f32;
+            let angle = start_angle - std::f32::consts::PI / state.total as f32 * num as f32;
 
             let direction = Vec2::new(angle.cos(), angle.sin());
 
             let bullet_pos = transform.translation + direction.extend(0.) * BOSS_SIZE / 2. * 0.8;
 
-            let id = commands
-                .spawn((
+            let id = pool
+                .acquire(&mut commands, BulletKind::Boss)
+                .insert((
                     Name::new("BossBullet"),
                     BossBullet,
                     EnemyBullet,
@@ -244,9 +354,14 @@ fn attack_system(
                         material: res.bullet_material.clone(),
                         ..default()
                     },
-                    Collider::ball(BOSS_BULLET_SIZE / 2. * 0.6),
-                    CollisionLayers::new([MyLayer::EnemyBullet], [MyLayer::Player]),
-                    RigidBody::Kinematic,
+                    BulletCollider::new(BOSS_BULLET_SIZE / 2. * 0.6, Team::Enemy),
+                    Cull {
+                        margin: BOSS_BULLET_SIZE,
+                    },
+                    Damage {
+                        amount: ENEMY_BULLET_DAMAGE,
+                        kind: DamageKind::Ice,
+                    },
                 ))
                 .id();
             debug!("boss position = {transform:?}");
@@ -254,29 +369,26 @@ fn attack_system(
 
             state.num += 1;
 
-            commands.spawn(AudioBundle {
-                source: enemy_res.bullet_sound.clone(),
-                settings: PlaybackSettings {
-                    mode: bevy::audio::PlaybackMode::Despawn,
-                    volume: Volume::Relative(VolumeLevel::new(0.2)),
-                    ..default()
-                },
-            });
+            sounds.send(SoundEvent(Sound::EnemyShot));
         }
     }
 }
 
-fn attack_end(mut commands: Commands, boss: Query<(Entity, &GlobalTransform, &AttackState)>) {
+fn attack_end(
+    mut commands: Commands,
+    boss: Query<(Entity, &GlobalTransform, &AttackState)>,
+    difficulty: Res<Difficulty>,
+) {
     let Ok((id, transform, state)) = boss.get_single() else {
         return;
     };
 
-    if state.num >= ATTACK_NUM {
+    if state.num >= state.total {
         debug!("Done Attack");
 
         // Spawn items
         let mut rng = rand::thread_rng();
-        for _ in 0..16 {
+        for _ in 0..difficulty.item_count(16) {
             let r = rng.gen::<f32>() * 50.0;
             let theta = rng.gen::<f32>() * std::f32::consts::PI * 2.0;
             let pos = Vec2::new(theta.cos(), theta.sin()) * r;
@@ -285,6 +397,7 @@ fn attack_end(mut commands: Commands, boss: Query<(Entity, &GlobalTransform, &At
                 SpatialBundle::from_transform(Transform::from_translation(
                     transform.translation() + pos.extend(0.0),
                 )),
+                StateScoped(GameState::Main),
             ));
         }
 
@@ -0,0 +1,254 @@
+use bevy::{prelude::*, utils::HashMap};
+
+/// Size of the cells bullets are sorted into.
+const CELL_SIZE: f32 = 64.0;
+
+pub struct BulletCollisionPlugin;
+
+impl Plugin for BulletCollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BulletHitEvent>()
+            .add_systems(Update, bullet_collisions.in_set(BulletCollisionSet));
+    }
+}
+
+/// Sends [`BulletHitEvent`]s. Systems reacting to hits run after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BulletCollisionSet;
+
+/// Side a bullet or hurtbox is on. Bullets only hit the other side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Team {
+    Player,
+    Enemy,
+}
+
+/// Circle of a bullet, tested against [`Hurtbox`]es without going through the physics. Bullets and
+/// hurtboxes are expected to have no parent.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct BulletCollider {
+    pub radius: f32,
+    pub team: Team,
+    /// Cleared while the bullet sits in the pool.
+    pub enabled: bool,
+}
+
+impl BulletCollider {
+    pub fn new(radius: f32, team: Team) -> Self {
+        Self {
+            radius,
+            team,
+            enabled: true,
+        }
+    }
+}
+
+/// Shape hit by the bullets of the other team.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Hurtbox {
+    pub shape: HurtboxShape,
+    pub team: Team,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum HurtboxShape {
+    Circle(f32),
+    /// Rectangle with the given half size, rotated with the entity.
+    Rect(Vec2),
+}
+
+impl HurtboxShape {
+    /// Radius of a circle around the shape.
+    fn bounding_radius(self) -> f32 {
+        match self {
+            HurtboxShape::Circle(radius) => radius,
+            HurtboxShape::Rect(half_size) => half_size.length(),
+        }
+    }
+
+    fn overlaps_circle(self, transform: &Transform, center: Vec2, radius: f32) -> bool {
+        match self {
+            HurtboxShape::Circle(r) => {
+                transform.translation.xy().distance_squared(center) <= (r + radius).powi(2)
+            }
+            HurtboxShape::Rect(half_size) => {
+                let local = transform
+                    .compute_affine()
+                    .inverse()
+                    .transform_point3(center.extend(0.))
+                    .xy();
+                let closest = local.clamp(-half_size, half_size);
+                local.distance_squared(closest) <= radius.powi(2)
+            }
+        }
+    }
+}
+
+/// Sent for every bullet overlapping a [`Hurtbox`] of the other team.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BulletHitEvent {
+    pub bullet: Entity,
+    pub target: Entity,
+}
+
+/// Bullets sorted by the cell their center is in.
+#[derive(Default)]
+struct BulletGrid {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2, BulletCollider)>>,
+    max_radius: f32,
+}
+
+impl BulletGrid {
+    fn cell(pos: Vec2) -> IVec2 {
+        (pos / CELL_SIZE).floor().as_ivec2()
+    }
+
+    fn clear(&mut self) {
+        // Keep the allocations around, the bullets are mostly in the same cells every frame.
+        self.cells.retain(|_, bullets| {
+            let used = !bullets.is_empty();
+            bullets.clear();
+            used
+        });
+        self.max_radius = 0.;
+    }
+
+    fn insert(&mut self, id: Entity, pos: Vec2, collider: BulletCollider) {
+        self.cells
+            .entry(Self::cell(pos))
+            .or_default()
+            .push((id, pos, collider));
+        self.max_radius = self.max_radius.max(collider.radius);
+    }
+
+    /// Bullets whose center is within `radius` plus the largest bullet radius of `center`.
+    fn near(
+        &self,
+        center: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = &(Entity, Vec2, BulletCollider)> {
+        let reach = Vec2::splat(radius + self.max_radius);
+        let min = Self::cell(center - reach);
+        let max = Self::cell(center + reach);
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+}
+
+fn bullet_collisions(
+    bullets: Query<(Entity, &Transform, &BulletCollider)>,
+    hurtboxes: Query<(Entity, &Transform, &Hurtbox)>,
+    mut grid: Local<BulletGrid>,
+    mut hits: EventWriter<BulletHitEvent>,
+) {
+    grid.clear();
+    for (id, transform, collider) in &bullets {
+        if !collider.enabled {
+            continue;
+        }
+        grid.insert(id, transform.translation.xy(), *collider);
+    }
+
+    for (target, transform, hurtbox) in &hurtboxes {
+        let center = transform.translation.xy();
+        for &(bullet, pos, collider) in grid.near(center, hurtbox.shape.bounding_radius()) {
+            if collider.team != hurtbox.team
+                && hurtbox
+                    .shape
+                    .overlaps_circle(transform, pos, collider.radius)
+            {
+                hits.send(BulletHitEvent { bullet, target });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn collider(radius: f32) -> BulletCollider {
+        BulletCollider::new(radius, Team::Enemy)
+    }
+
+    fn near(grid: &BulletGrid, center: Vec2, radius: f32) -> Vec<Entity> {
+        let mut ids: Vec<_> = grid.near(center, radius).map(|&(id, ..)| id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn near_finds_bullets_in_neighbouring_cells() {
+        let mut grid = BulletGrid::default();
+        let inside = Entity::from_raw(0);
+        let left = Entity::from_raw(1);
+        let far = Entity::from_raw(2);
+        grid.insert(inside, Vec2::new(CELL_SIZE + 4., 4.), collider(2.));
+        grid.insert(left, Vec2::new(CELL_SIZE - 1., 4.), collider(2.));
+        grid.insert(far, Vec2::new(10. * CELL_SIZE, 0.), collider(2.));
+
+        assert_eq!(
+            near(&grid, Vec2::new(CELL_SIZE + 1., 4.), 4.),
+            vec![inside, left]
+        );
+    }
+
+    #[test]
+    fn near_reaches_by_the_largest_bullet() {
+        let mut grid = BulletGrid::default();
+        let big = Entity::from_raw(0);
+        grid.insert(
+            big,
+            Vec2::new(-1.5 * CELL_SIZE, 0.),
+            collider(2. * CELL_SIZE),
+        );
+
+        assert_eq!(near(&grid, Vec2::new(1., 0.), 1.), vec![big]);
+    }
+
+    #[test]
+    fn clear_empties_the_grid() {
+        let mut grid = BulletGrid::default();
+        grid.insert(Entity::from_raw(0), Vec2::ZERO, collider(2.));
+        grid.clear();
+
+        assert!(near(&grid, Vec2::ZERO, 10.).is_empty());
+        assert_eq!(grid.max_radius, 0.);
+    }
+
+    #[test]
+    fn circle_overlaps_on_edge_contact() {
+        let shape = HurtboxShape::Circle(10.);
+        let transform = Transform::from_xyz(5., 0., 0.);
+
+        assert!(shape.overlaps_circle(&transform, Vec2::new(20., 0.), 5.));
+        assert!(!shape.overlaps_circle(&transform, Vec2::new(20.1, 0.), 5.));
+    }
+
+    #[test]
+    fn rect_overlaps_on_edge_contact() {
+        let shape = HurtboxShape::Rect(Vec2::new(10., 2.));
+        let transform = Transform::IDENTITY;
+
+        assert!(shape.overlaps_circle(&transform, Vec2::new(13., 0.), 3.));
+        assert!(!shape.overlaps_circle(&transform, Vec2::new(13., 0.), 2.9));
+        // Near a corner the distance is to the corner, not to the extended edges.
+        assert!(!shape.overlaps_circle(&transform, Vec2::new(12., 4.), 2.));
+        assert!(shape.overlaps_circle(&transform, Vec2::new(12., 4.), 3.));
+    }
+
+    #[test]
+    fn rect_rotates_with_the_entity() {
+        let shape = HurtboxShape::Rect(Vec2::new(10., 2.));
+        let transform =
+            Transform::from_xyz(100., 50., 0.).with_rotation(Quat::from_rotation_z(FRAC_PI_2));
+
+        // The long side now points along y.
+        assert!(shape.overlaps_circle(&transform, Vec2::new(100., 59.), 1.));
+        assert!(!shape.overlaps_circle(&transform, Vec2::new(109., 50.), 1.));
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::{ecs::system::EntityCommands, prelude::*, utils::HashMap};
+
+use crate::{bullet_collision::BulletCollider, GameState};
+
+pub struct BulletPoolPlugin;
+
+impl Plugin for BulletPoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BulletPool>().add_systems(
+            OnExit(GameState::GameOver),
+            |mut commands: Commands, bullets: Query<(Entity, &PooledBullet)>| {
+                for (id, bullet) in &bullets {
+                    if bullet.active {
+                        release_bullet(&mut commands, id);
+                    }
+                }
+            },
+        );
+    }
+}
+
+/// Bullets with the same components, reused for each other.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BulletKind {
+    Player,
+    Enemy,
+    Boss,
+}
+
+/// Released bullets, reused instead of spawning new ones. They keep their components, hidden and
+/// with their collider disabled, so that reusing one doesn't move it to another archetype.
+/// They stay around between runs, so bullets aren't [`StateScoped`](crate::state_scoped::StateScoped).
+#[derive(Resource, Default)]
+pub struct BulletPool {
+    free: HashMap<BulletKind, Vec<Entity>>,
+}
+
+#[derive(Component)]
+pub struct PooledBullet {
+    kind: BulletKind,
+    active: bool,
+}
+
+impl PooledBullet {
+    /// Whether the bullet is in use. Systems moving bullets skip released ones.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+impl BulletPool {
+    /// Reuses a released bullet of the kind or spawns a new one. The caller inserts all the
+    /// components of the kind, overwriting the old values.
+    pub fn acquire<'w, 's, 'a>(
+        &mut self,
+        commands: &'a mut Commands<'w, 's>,
+        kind: BulletKind,
+    ) -> EntityCommands<'w, 's, 'a> {
+        let free = self.free.entry(kind).or_default();
+        while let Some(id) = free.pop() {
+            if commands.get_entity(id).is_some() {
+                let mut bullet = commands.entity(id);
+                bullet.insert(PooledBullet { kind, active: true });
+                return bullet;
+            }
+        }
+        commands.spawn(PooledBullet { kind, active: true })
+    }
+
+    #[cfg(feature = "dev")]
+    pub fn free(&self) -> usize {
+        self.free.values().map(Vec::len).sum()
+    }
+}
+
+/// Returns the bullet to the [`BulletPool`]. Releasing a bullet twice, e.g. when it hits something
+/// as it leaves the screen, only frees it once.
+pub fn release_bullet(commands: &mut Commands, id: Entity) {
+    commands.add(move |world: &mut World| {
+        let Some(mut bullet) = world.get_entity_mut(id) else {
+            return;
+        };
+        let kind = match bullet.get_mut::<PooledBullet>() {
+            Some(mut pooled) if pooled.active => {
+                pooled.active = false;
+                pooled.kind
+            }
+            _ => return,
+        };
+        if let Some(mut collider) = bullet.get_mut::<BulletCollider>() {
+            collider.enabled = false;
+        }
+        if let Some(mut visibility) = bullet.get_mut::<Visibility>() {
+            *visibility = Visibility::Hidden;
+        }
+        world
+            .resource_mut::<BulletPool>()
+            .free
+            .entry(kind)
+            .or_default()
+            .push(id);
+    });
+}
@@ -0,0 +1,167 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use rand::Rng;
+
+use crate::{
+    boss::BossPhaseChanged,
+    damage::{BossDiedEvent, PlayerHitEvent},
+    pause::PauseState,
+    GameState, MainCamera,
+};
+
+/// Trauma lost per second.
+const TRAUMA_DECAY: f32 = 1.5;
+
+const MAX_SHAKE_OFFSET: f32 = 20.0;
+
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+
+/// Zoom lost per second.
+const PUNCH_DECAY: f32 = 0.5;
+
+pub struct CameraEffectsPlugin;
+
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraEffects>()
+            // Everything between these two systems sees the logical camera position.
+            .add_systems(PreUpdate, remove_camera_effects)
+            .add_systems(
+                PostUpdate,
+                apply_camera_effects.before(TransformSystem::TransformPropagate),
+            )
+            .add_systems(Update, update_slow_motion)
+            .add_systems(
+                Update,
+                (
+                    (|mut effects: ResMut<CameraEffects>,
+                      mut events: EventReader<PlayerHitEvent>| {
+                        // A single bullet on normal difficulty adds 0.4.
+                        for PlayerHitEvent(area) in events.read() {
+                            effects.add_trauma(0.2 + area / 400.);
+                        }
+                    }),
+                    (|mut effects: ResMut<CameraEffects>,
+                      mut events: EventReader<BossPhaseChanged>| {
+                        // Later phases hit harder.
+                        for &BossPhaseChanged(phase) in events.read() {
+                            effects.add_trauma(0.4 + 0.2 * phase as f32);
+                            effects.punch(0.05 + 0.05 * phase as f32);
+                        }
+                    }),
+                    (|mut effects: ResMut<CameraEffects>| {
+                        effects.add_trauma(1.0);
+                        effects.punch(0.2);
+                        effects.slow_motion(0.3, 1.5);
+                    })
+                    .run_if(on_event::<BossDiedEvent>()),
+                ),
+            )
+            // The boss dies on the last frame of the run, its effects play over the game over screen.
+            .add_systems(
+                OnExit(GameState::GameOver),
+                |mut effects: ResMut<CameraEffects>, mut time: ResMut<Time<Virtual>>| {
+                    effects.trauma = 0.;
+                    effects.punch = 0.;
+                    effects.slow_motion = None;
+                    time.set_relative_speed(1.);
+                },
+            );
+    }
+}
+
+/// Shake, zoom and slow motion layered over the camera.
+#[derive(Resource, Default)]
+pub struct CameraEffects {
+    /// Shake strength from 0 to 1. The shake grows with its square.
+    trauma: f32,
+    /// Zoom in as a fraction of the view.
+    punch: f32,
+    /// Speed of the game and the real seconds left.
+    slow_motion: Option<(f32, f32)>,
+    applied: Option<Applied>,
+}
+
+struct Applied {
+    offset: Vec3,
+    angle: f32,
+}
+
+impl CameraEffects {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.);
+    }
+
+    pub fn punch(&mut self, amount: f32) {
+        self.punch = self.punch.max(amount);
+    }
+
+    pub fn slow_motion(&mut self, speed: f32, secs: f32) {
+        self.slow_motion = Some((speed, secs));
+    }
+}
+
+pub fn remove_camera_effects(
+    mut effects: ResMut<CameraEffects>,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    let Some(applied) = effects.applied.take() else {
+        return;
+    };
+    let mut transform = camera.single_mut();
+    transform.translation -= applied.offset;
+    transform.rotate_z(-applied.angle);
+    transform.scale = Vec3::ONE;
+}
+
+fn apply_camera_effects(
+    mut effects: ResMut<CameraEffects>,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+    time: Res<Time<Real>>,
+    pause_state: Res<State<PauseState>>,
+) {
+    // Freeze the camera while paused.
+    if *pause_state.get() != PauseState::Running {
+        return;
+    }
+
+    let dt = time.delta_seconds();
+    effects.trauma = (effects.trauma - TRAUMA_DECAY * dt).max(0.);
+    effects.punch = (effects.punch - PUNCH_DECAY * dt).max(0.);
+    if effects.trauma <= 0. && effects.punch <= 0. {
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+    let shake = effects.trauma * effects.trauma;
+    let applied = Applied {
+        offset: Vec3::new(rng.gen::<f32>() * 2. - 1., rng.gen::<f32>() * 2. - 1., 0.)
+            * MAX_SHAKE_OFFSET
+            * shake,
+        angle: (rng.gen::<f32>() * 2. - 1.) * MAX_SHAKE_ANGLE * shake,
+    };
+
+    let mut transform = camera.single_mut();
+    transform.translation += applied.offset;
+    transform.rotate_z(applied.angle);
+    // Scaling the camera instead of its projection keeps the background filling the view.
+    transform.scale = Vec3::splat(1. - effects.punch);
+    effects.applied = Some(applied);
+}
+
+fn update_slow_motion(
+    mut effects: ResMut<CameraEffects>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+) {
+    let Some((speed, secs)) = effects.slow_motion.as_mut() else {
+        return;
+    };
+
+    *secs -= real_time.delta_seconds();
+    if *secs > 0. {
+        virtual_time.set_relative_speed(*speed);
+    } else {
+        virtual_time.set_relative_speed(1.);
+        effects.slow_motion = None;
+    }
+}
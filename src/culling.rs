@@ -0,0 +1,58 @@
+use bevy::{ecs::query::Has, prelude::*};
+
+use crate::{
+    bullet_pool::{release_bullet, PooledBullet},
+    enemy::{Enemy, EnemyEscapedEvent},
+    MainCamera, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+pub struct CullingPlugin;
+
+impl Plugin for CullingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, cull_off_screen);
+    }
+}
+
+/// Removes the entity once it is more than `margin` outside the view. Pooled bullets are released
+/// instead of despawned.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Cull {
+    pub margin: f32,
+}
+
+#[allow(clippy::type_complexity)]
+fn cull_off_screen(
+    mut commands: Commands,
+    q: Query<(Entity, &Transform, &Cull, Option<&PooledBullet>, Has<Enemy>)>,
+    camera: Query<&Transform, With<MainCamera>>,
+    mut escaped: EventWriter<EnemyEscapedEvent>,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    let view = Rect::from_center_size(
+        camera.translation.xy(),
+        Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT),
+    );
+
+    for (id, transform, cull, pooled, enemy) in &q {
+        // Released bullets wait in the pool wherever they were.
+        if pooled.is_some_and(|pooled| !pooled.is_active()) {
+            continue;
+        }
+        let pos = transform.translation.xy();
+        if view.inset(cull.margin).contains(pos) {
+            continue;
+        }
+
+        if pooled.is_some() {
+            release_bullet(&mut commands, id);
+        } else {
+            if enemy {
+                escaped.send(EnemyEscapedEvent);
+            }
+            commands.entity(id).despawn_recursive();
+        }
+    }
+}
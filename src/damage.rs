@@ -1,125 +1,197 @@
 use bevy::{
-    audio::{Volume, VolumeLevel},
     prelude::*,
+    utils::{HashMap, HashSet},
 };
+#[cfg(feature = "dev")]
 use bevy_debug_text_overlay::screen_print;
-use bevy_xpbd_2d::prelude::*;
 use rand::Rng;
 
 use crate::{
+    audio::{Sound, SoundEvent},
     boss::Boss,
+    bullet_collision::{BulletCollisionSet, BulletHitEvent},
+    bullet_pool::release_bullet,
+    difficulty::Difficulty,
     enemy::{Enemy, EnemyBullet},
-    health::Health,
-    item::Item,
+    health::{DamageEvent, DamageKind, Died, Health, HealthSet},
+    lives::Invulnerable,
     player::{Player, PlayerBullet},
 };
 
 #[derive(Event)]
 pub struct BossDiedEvent;
 
-#[derive(Resource)]
-struct DamageRes {
-    hit_sound: Handle<AudioSource>,
+/// Sent when enemy bullets hit the player, with the area lost.
+#[derive(Event)]
+pub struct PlayerHitEvent(pub f32);
+
+fn boss_died(
+    mut died_events: EventReader<Died>,
+    bosses: Query<(), With<Boss>>,
+    mut boss_died_event: EventWriter<BossDiedEvent>,
+) {
+    for ev in died_events.read() {
+        if bosses.contains(ev.entity) {
+            boss_died_event.send(BossDiedEvent);
+        }
+    }
+}
+
+/// Damage dealt by a bullet on hit.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Damage {
+    pub amount: f32,
+    pub kind: DamageKind,
+}
+
+/// Damage of an enemy bullet, in area of the player's circle.
+pub const ENEMY_BULLET_DAMAGE: f32 = 80.0;
+
+/// Damage multipliers per [`DamageKind`]. Kinds not listed take normal damage.
+#[derive(Component, Clone, Default, Debug)]
+pub struct Resistances(HashMap<DamageKind, f32>);
+
+impl Resistances {
+    pub fn with(mut self, kind: DamageKind, multiplier: f32) -> Self {
+        self.0.insert(kind, multiplier);
+        self
+    }
+
+    pub fn multiplier(&self, kind: DamageKind) -> f32 {
+        self.0.get(&kind).copied().unwrap_or(1.0)
+    }
+}
+
+/// Chance of a bullet dealing extra damage.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Critical {
+    pub chance: f32,
+    pub multiplier: f32,
+}
+
+/// Area of the entity taking extra damage from bullets hitting it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct WeakPoint {
+    /// Center of the area, relative to the entity.
+    pub offset: Vec2,
+    pub radius: f32,
+    pub multiplier: f32,
 }
 
-fn startup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(DamageRes {
-        hit_sound: asset_server.load("sounds/explosion.ogg"),
-    });
+impl WeakPoint {
+    fn contains(&self, transform: &Transform, point: Vec3) -> bool {
+        let local = transform.compute_matrix().inverse().transform_point3(point);
+        local.xy().distance(self.offset) <= self.radius
+    }
 }
 
+#[allow(clippy::type_complexity)]
 fn enemy_damage(
     mut commands: Commands,
-    mut enemies: Query<
+    mut hits: EventReader<BulletHitEvent>,
+    enemies: Query<
         (
-            Entity,
-            &CollidingEntities,
-            &mut Health,
+            &Health,
             &Transform,
+            Option<&Resistances>,
+            Option<&WeakPoint>,
             Has<Boss>,
         ),
         Or<(With<Enemy>, With<Boss>)>,
     >,
-    player_bullets: Query<(), With<PlayerBullet>>,
-    res: Res<DamageRes>,
-    mut boss_died_event: EventWriter<BossDiedEvent>,
+    player_bullets: Query<(&Damage, Option<&Critical>, &Transform), With<PlayerBullet>>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut sounds: EventWriter<SoundEvent>,
 ) {
-    let mut hit_any = false;
+    let mut rng = rand::thread_rng();
 
-    for (enemy_id, colliding_entities, mut health, &transform, is_boss) in &mut enemies {
+    let mut hit = HashSet::new();
+    let mut used = HashSet::new();
+    for &BulletHitEvent { bullet, target } in hits.read() {
+        let Ok((health, transform, resistances, weak_point, is_boss)) = enemies.get(target) else {
+            continue;
+        };
         if is_boss {
             let angle = transform.rotation.to_axis_angle().1 / std::f32::consts::PI;
-            if angle < 0.5 || angle > 1.5{
+            if !(0.5..=1.5).contains(&angle) {
                 // screen_print!("boss is not accepting damage: {}", angle);
                 continue;
             }
             // screen_print!("boss is accepting damage: {}", angle);
         }
-        let mut hit = false;
-        for &entity in colliding_entities.iter() {
-            if player_bullets.contains(entity) && health.health > 0. {
-                hit = true;
-                commands.entity(entity).despawn();
-                health.health -= 2.;
-                if health.health <= 0. {
-                    health.health = 0.;
-                    commands.entity(enemy_id).despawn_recursive();
-                    // screen_print!(push, "Enemy died!");
-
-                    if is_boss {
-                        boss_died_event.send(BossDiedEvent);
-                    }
-
-                    let mut rng = rand::thread_rng();
-                    for _ in 0..32 {
-                        let dev = Vec2::new(rng.gen::<f32>() - 0.5, rng.gen::<f32>() - 0.5) * 100.0;
-
-                        commands.spawn((
-                            Item,
-                            SpatialBundle::from_transform(Transform::from_translation(
-                                transform.translation + dev.extend(0.0),
-                            )),
-                        ));
-                    }
-                }
-            }
+        if health.health <= 0. {
+            continue;
+        }
+
+        let Ok((damage, critical, bullet_transform)) = player_bullets.get(bullet) else {
+            continue;
+        };
+        // A bullet overlapping several enemies only hits the first one.
+        if !used.insert(bullet) {
+            continue;
         }
+        hit.insert(target);
+        release_bullet(&mut commands, bullet);
 
-        if hit && !hit_any {
-            commands.spawn(AudioBundle {
-                source: res.hit_sound.clone(),
-                settings: PlaybackSettings::DESPAWN
-                    .with_volume(Volume::Relative(VolumeLevel::new(0.5))),
-            });
+        let mut amount = damage.amount;
+        if let Some(resistances) = resistances {
+            amount *= resistances.multiplier(damage.kind);
         }
-        hit_any |= hit;
+        if let Some(weak_point) = weak_point {
+            if weak_point.contains(transform, bullet_transform.translation) {
+                amount *= weak_point.multiplier;
+            }
+        }
+        let critical = critical.filter(|c| rng.gen::<f32>() < c.chance);
+        if let Some(critical) = critical {
+            amount *= critical.multiplier;
+        }
+
+        damage_events.send(DamageEvent {
+            target,
+            amount,
+            source: Some(bullet),
+            kind: damage.kind,
+            critical: critical.is_some(),
+            position: Some(bullet_transform.translation.xy()),
+        });
+    }
+
+    // One hit sound per enemy hit.
+    for _ in hit {
+        sounds.send(SoundEvent(Sound::Hit));
     }
 }
 
 fn player_damage(
     mut commands: Commands,
-    mut player: Query<(&CollidingEntities, &mut Player), With<Player>>,
-    enemy_bullets: Query<(), With<EnemyBullet>>,
-    res: Res<DamageRes>,
+    mut hits: EventReader<BulletHitEvent>,
+    mut player: Query<(Entity, &mut Player, Option<&Resistances>), Without<Invulnerable>>,
+    enemy_bullets: Query<&Damage, With<EnemyBullet>>,
+    mut sounds: EventWriter<SoundEvent>,
+    mut hit_events: EventWriter<PlayerHitEvent>,
+    difficulty: Res<Difficulty>,
 ) {
-    let Ok((colliding_entities, mut player)) = player.get_single_mut() else {
+    let Ok((player_id, mut player, resistances)) = player.get_single_mut() else {
+        hits.clear();
         return;
     };
 
-    let mut colliding_bullets = 0;
-    for &entity in colliding_entities.iter() {
-        if enemy_bullets.contains(entity) {
-            colliding_bullets += 1;
-            commands.entity(entity).despawn_recursive();
+    let mut amount = 0.;
+    let mut hit = false;
+    for ev in hits.read().filter(|ev| ev.target == player_id) {
+        if let Ok(damage) = enemy_bullets.get(ev.bullet) {
+            hit = true;
+            amount += damage.amount * resistances.map_or(1.0, |r| r.multiplier(damage.kind));
+            release_bullet(&mut commands, ev.bullet);
         }
     }
-    if colliding_bullets > 0 {
-        player.increase(-colliding_bullets as f32 * 80.);
+    if hit {
+        let amount = amount * difficulty.damage();
+        player.increase(-amount);
+        hit_events.send(PlayerHitEvent(amount));
 
-        commands.spawn(AudioBundle {
-            source: res.hit_sound.clone(),
-            settings: PlaybackSettings::DESPAWN.with_volume(Volume::Relative(VolumeLevel::new(0.5))),
-        });
+        sounds.send(SoundEvent(Sound::Hit));
     }
 }
 
@@ -127,8 +199,12 @@ pub struct DamagePlugin;
 
 impl Plugin for DamagePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<BossDiedEvent>();
-        app.add_systems(Startup, startup);
-        app.add_systems(Update, (enemy_damage, player_damage));
+        app.add_event::<BossDiedEvent>()
+            .add_event::<PlayerHitEvent>();
+        app.add_systems(
+            Update,
+            (enemy_damage, player_damage).after(BulletCollisionSet),
+        );
+        app.add_systems(PostUpdate, boss_died.in_set(HealthSet::Death));
     }
 }
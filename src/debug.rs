@@ -0,0 +1,389 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::system::SystemParam,
+    input::{common_conditions::input_toggle_active, InputSystem},
+    prelude::*,
+    window::ReceivedCharacter,
+};
+use bevy_debug_text_overlay::{screen_print, OverlayPlugin};
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_xpbd_2d::prelude::*;
+use leafwing_input_manager::plugin::InputManagerSystem;
+
+use crate::{
+    bench::{scatter_bullets, BENCH_BULLETS},
+    boss::{self, Boss},
+    bullet_collision::BulletCollider,
+    bullet_pool::{release_bullet, BulletPool},
+    enemy::{
+        spawn_enemy, Enemy, EnemyBullet, EnemyController, EnemyKind, EnemyResource, ENEMY_SIZE,
+    },
+    health::{DamageEvent, Health},
+    item::Item,
+    level::{boss_camera_y, Level},
+    lives::Invulnerable,
+    particle::Particle,
+    player::{Player, PlayerBullet},
+    GameState, MainCamera, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+/// How long the overlay lines stay after their toggle is turned off.
+const OVERLAY_SECS: f32 = 0.1;
+
+/// Number of output lines kept in the console.
+const CONSOLE_LINES: usize = 8;
+
+const HELP: &str = "god | radius <r> | kill | boss | spawn <normal|ice> | bullets [n]";
+
+/// F1: FPS, F2: colliders, F3: entity counts, F4: boss state, F5: inspector, `: console.
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(OverlayPlugin::default())
+            .add_plugins(PhysicsDebugPlugin::default())
+            .insert_resource(PhysicsDebugConfig {
+                enabled: false,
+                ..default()
+            })
+            .add_plugins(
+                WorldInspectorPlugin::new().run_if(input_toggle_active(false, KeyCode::F5)),
+            )
+            .init_resource::<Console>()
+            .add_systems(Startup, spawn_console)
+            .add_systems(
+                PreUpdate,
+                (
+                    console_input,
+                    // Typing in the console doesn't move the player or pause the game.
+                    |console: Res<Console>, mut keys: ResMut<Input<KeyCode>>| {
+                        if console.open {
+                            keys.reset_all();
+                        }
+                    },
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .before(InputManagerSystem::Update),
+            )
+            .add_systems(
+                Update,
+                (
+                    (|diagnostics: Res<DiagnosticsStore>| {
+                        let average = |id| {
+                            diagnostics
+                                .get(id)
+                                .and_then(|d| d.average())
+                                .unwrap_or_default()
+                        };
+                        screen_print!(
+                            sec: OVERLAY_SECS,
+                            "FPS: {:.0} ({:.2} ms)",
+                            average(FrameTimeDiagnosticsPlugin::FPS),
+                            average(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+                        );
+                    })
+                    .run_if(input_toggle_active(false, KeyCode::F1)),
+                    (|keys: Res<Input<KeyCode>>, mut config: ResMut<PhysicsDebugConfig>| {
+                        if keys.just_pressed(KeyCode::F2) {
+                            config.enabled = !config.enabled;
+                        }
+                    }),
+                    // Bullets don't go through the physics, so their colliders are drawn here.
+                    (|config: Res<PhysicsDebugConfig>,
+                      bullets: Query<(&Transform, &BulletCollider)>,
+                      mut gizmos: Gizmos| {
+                        if !config.enabled {
+                            return;
+                        }
+                        for (transform, collider) in &bullets {
+                            if !collider.enabled {
+                                continue;
+                            }
+                            gizmos.circle_2d(
+                                transform.translation.xy(),
+                                collider.radius,
+                                Color::ORANGE,
+                            );
+                        }
+                    }),
+                    entity_counts.run_if(input_toggle_active(false, KeyCode::F3)),
+                    (|boss: Query<EntityRef, With<Boss>>| {
+                        for boss in &boss {
+                            screen_print!(sec: OVERLAY_SECS, "Boss: {}", boss::state_name(boss));
+                        }
+                    })
+                    .run_if(input_toggle_active(false, KeyCode::F4)),
+                    update_console_text,
+                ),
+            );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn entity_counts(
+    all: Query<()>,
+    enemies: Query<(), With<Enemy>>,
+    bosses: Query<(), With<Boss>>,
+    player_bullets: Query<(), With<PlayerBullet>>,
+    enemy_bullets: Query<(), With<EnemyBullet>>,
+    items: Query<(), With<Item>>,
+    particles: Query<(), With<Particle>>,
+    pool: Res<BulletPool>,
+) {
+    screen_print!(
+        sec: OVERLAY_SECS,
+        "Entities: {} | enemies {} boss {} player bullets {} enemy bullets {} items {} particles {}",
+        all.iter().count(),
+        enemies.iter().count(),
+        bosses.iter().count(),
+        player_bullets.iter().count(),
+        enemy_bullets.iter().count(),
+        items.iter().count(),
+        particles.iter().count(),
+    );
+    screen_print!(sec: OVERLAY_SECS, "Pooled bullets: {}", pool.free());
+}
+
+#[derive(Resource, Default)]
+struct Console {
+    open: bool,
+    input: String,
+    output: Vec<String>,
+}
+
+impl Console {
+    fn print(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        if self.output.len() > CONSOLE_LINES {
+            self.output.remove(0);
+        }
+    }
+}
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn spawn_console(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Console"),
+        ConsoleText,
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.,
+                    color: Color::YELLOW,
+                    ..default()
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(8.),
+                bottom: Val::Px(8.),
+                ..default()
+            },
+            background_color: Color::BLACK.with_a(0.6).into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(20),
+            ..default()
+        },
+    ));
+}
+
+fn update_console_text(
+    console: Res<Console>,
+    mut text: Query<(&mut Text, &mut Visibility), With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut vis)) = text.get_single_mut() else {
+        return;
+    };
+    *vis = if console.open {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    let mut value = console.output.join("\n");
+    value.push_str(&format!("\n> {}_", console.input));
+    text.sections[0].value = value;
+}
+
+// The grave key opens the console, Enter runs the typed command.
+fn console_input(
+    mut commands: Commands,
+    mut console: ResMut<Console>,
+    keys: Res<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    state: Res<State<GameState>>,
+    mut cheats: Cheats,
+) {
+    if keys.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        chars.clear();
+        return;
+    }
+    if !console.open {
+        chars.clear();
+        return;
+    }
+
+    for ev in chars.read() {
+        if !ev.char.is_control() && ev.char != '`' {
+            console.input.push(ev.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if !keys.just_pressed(KeyCode::Return) {
+        return;
+    }
+
+    let line = std::mem::take(&mut console.input);
+    let mut args = line.split_whitespace();
+    let Some(command) = args.next() else {
+        return;
+    };
+    console.print(format!("> {line}"));
+    if *state.get() != GameState::Main && command != "help" {
+        console.print("Only available in game");
+        return;
+    }
+
+    let output = match command {
+        "help" => HELP.to_string(),
+        "god" => cheats.toggle_god(&mut commands),
+        "radius" => match args.next().and_then(|r| r.parse().ok()) {
+            Some(radius) => cheats.set_radius(radius),
+            None => "Usage: radius <r>".to_string(),
+        },
+        "kill" => cheats.kill_enemies(),
+        "boss" => cheats.jump_to_boss(&mut commands),
+        "spawn" => match args.next() {
+            Some("normal") => cheats.spawn(&mut commands, EnemyKind::Normal),
+            Some("ice") => cheats.spawn(&mut commands, EnemyKind::Ice),
+            _ => "Usage: spawn <normal|ice>".to_string(),
+        },
+        "bullets" => match args.next().map(str::parse) {
+            None => cheats.bench_bullets(&mut commands, BENCH_BULLETS),
+            Some(Ok(count)) => cheats.bench_bullets(&mut commands, count),
+            Some(Err(_)) => "Usage: bullets [n]".to_string(),
+        },
+        _ => format!("Unknown command, try: {HELP}"),
+    };
+    console.print(output);
+}
+
+#[derive(SystemParam)]
+struct Cheats<'w, 's> {
+    player: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static mut Player,
+            &'static mut Transform,
+            Option<&'static Invulnerable>,
+        ),
+        Without<MainCamera>,
+    >,
+    enemies: Query<'w, 's, (Entity, &'static Health), With<Enemy>>,
+    enemy_bullets: Query<'w, 's, Entity, With<EnemyBullet>>,
+    camera: Query<'w, 's, &'static mut Transform, (With<MainCamera>, Without<Player>)>,
+    level: Option<ResMut<'w, Level>>,
+    damage: EventWriter<'w, DamageEvent>,
+    pool: ResMut<'w, BulletPool>,
+    enemy_res: Res<'w, EnemyResource>,
+}
+
+impl Cheats<'_, '_> {
+    fn toggle_god(&mut self, commands: &mut Commands) -> String {
+        let Ok((id, _, _, invulnerable)) = self.player.get_single() else {
+            return "No player".to_string();
+        };
+        if invulnerable.is_some() {
+            commands.entity(id).remove::<Invulnerable>();
+            "God mode off".to_string()
+        } else {
+            commands.entity(id).insert(Invulnerable::forever());
+            "God mode on".to_string()
+        }
+    }
+
+    fn set_radius(&mut self, radius: f32) -> String {
+        let Ok((_, mut player, _, _)) = self.player.get_single_mut() else {
+            return "No player".to_string();
+        };
+        player.radius = radius.clamp(1., 40.);
+        format!("Radius set to {}", player.radius)
+    }
+
+    fn kill_enemies(&mut self) -> String {
+        for (id, health) in &self.enemies {
+            self.damage.send(DamageEvent {
+                target: id,
+                amount: health.max_health,
+                source: None,
+                kind: default(),
+                critical: false,
+                position: None,
+            });
+        }
+        format!("Killed {} enemies", self.enemies.iter().count())
+    }
+
+    fn jump_to_boss(&mut self, commands: &mut Commands) -> String {
+        let Some(level) = self.level.as_mut() else {
+            return "No level".to_string();
+        };
+        let Some(boss_pos) = level.boss_pos else {
+            return "Boss already spawned".to_string();
+        };
+        let camera_y = boss_camera_y(boss_pos);
+        level
+            .enemies
+            .retain(|(_, enemy)| enemy.attack_pos.last().unwrap().y <= camera_y);
+
+        for (id, _) in &self.enemies {
+            commands.entity(id).despawn_recursive();
+        }
+        for id in &self.enemy_bullets {
+            release_bullet(commands, id);
+        }
+
+        let mut camera = self.camera.single_mut();
+        let offset = camera_y - camera.translation.y;
+        camera.translation.y = camera_y;
+        if let Ok((_, _, mut tf, _)) = self.player.get_single_mut() {
+            tf.translation.y += offset;
+        }
+        "Jumped to the boss".to_string()
+    }
+
+    fn spawn(&mut self, commands: &mut Commands, kind: EnemyKind) -> String {
+        let camera = self.camera.single().translation.xy();
+        let pos = camera + Vec2::Y * (SCREEN_HEIGHT + ENEMY_SIZE) / 2.;
+        // Stop in the middle, then leave to the right.
+        let waypoints = vec![
+            camera + Vec2::new((SCREEN_WIDTH + ENEMY_SIZE) / 2., 100.),
+            camera + Vec2::Y * 100.,
+        ];
+        spawn_enemy(
+            commands,
+            pos,
+            EnemyController::from(waypoints).with_kind(kind),
+        );
+        format!("Spawned {kind:?} enemy")
+    }
+
+    // Fills the screen with still enemy bullets, e.g. with god mode on and the FPS shown. The
+    // `--bench-bullets` flag measures the same without the console.
+    fn bench_bullets(&mut self, commands: &mut Commands, count: usize) -> String {
+        let camera = self.camera.single().translation.xy();
+        scatter_bullets(commands, &mut self.pool, &self.enemy_res, camera, count);
+        format!("Spawned {count} bullets, {} pooled", self.pool.free())
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>();
+    }
+}
+
+/// Difficulty of the current run, chosen on the title screen.
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Lunatic,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Lunatic,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Lunatic => "Lunatic",
+        }
+    }
+
+    /// The next difficulty, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&d| d == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// The previous difficulty, wrapping around.
+    pub fn prev(self) -> Self {
+        let i = Self::ALL.iter().position(|&d| d == self).unwrap();
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Multiplier for the number of bullets in enemy and boss attacks.
+    pub fn bullet_density(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+            Difficulty::Lunatic => 2.0,
+        }
+    }
+
+    /// Multiplier for the speed of enemy bullets.
+    pub fn bullet_speed(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+            Difficulty::Lunatic => 1.5,
+        }
+    }
+
+    /// Multiplier for the damage the player takes from enemy bullets.
+    pub fn damage(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+            Difficulty::Lunatic => 2.0,
+        }
+    }
+
+    /// Multiplier for the number of dropped items.
+    pub fn item_drops(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.75,
+            Difficulty::Lunatic => 0.5,
+        }
+    }
+
+    /// Number of bullets for an attack of `base` bullets on normal difficulty.
+    pub fn bullet_count(self, base: u32) -> u32 {
+        ((base as f32 * self.bullet_density()).round() as u32).max(1)
+    }
+
+    /// Number of items for a drop of `base` items on normal difficulty.
+    pub fn item_count(self, base: u32) -> u32 {
+        (base as f32 * self.item_drops()).round() as u32
+    }
+}
@@ -0,0 +1,283 @@
+use ab_glyph::{point, Font as _, FontArc, PxScale, ScaleFont as _};
+use bevy::{prelude::*, sprite::Mesh2dHandle};
+
+use crate::{damage::BossDiedEvent, lives::GameOverEvent, GameState, MainCamera};
+
+const DROP_RADIUS: f32 = 6.0;
+
+/// Speed of the drops flying away when splashing.
+const SPLASH_SPEED: f32 = 1000.0;
+
+const SPLASH_DISTANCE: f32 = 1000.0;
+
+/// Coverage of the mask above which a drop is placed.
+const COVERAGE_THRESHOLD: f32 = 0.5;
+
+pub struct DropTextPlugin;
+
+impl Plugin for DropTextPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, startup)
+            .add_systems(Update, (build_drop_text, splash_drops))
+            .add_systems(
+                PostUpdate,
+                (
+                    (|commands: Commands, camera: Query<Entity, With<MainCamera>>| {
+                        spawn_result_text(commands, camera, "GAME OVER", Color::GRAY);
+                    })
+                    .run_if(on_event::<GameOverEvent>()),
+                    (|commands: Commands, camera: Query<Entity, With<MainCamera>>| {
+                        spawn_result_text(commands, camera, "STAGE CLEAR", Color::CYAN);
+                    })
+                    .run_if(on_event::<BossDiedEvent>()),
+                ),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                |mut commands: Commands, texts: Query<Entity, With<ResultText>>| {
+                    for e in &texts {
+                        commands.entity(e).insert(Splash);
+                    }
+                },
+            );
+    }
+}
+
+#[derive(Resource)]
+struct DropTextAssets {
+    mesh: Mesh2dHandle,
+}
+
+/// Text drawn with rain drops, spawned as children once the default font is available.
+#[derive(Component)]
+pub struct DropText {
+    pub text: String,
+    /// Font size in pixels.
+    pub size: f32,
+    /// Distance between neighbouring drops.
+    pub spacing: f32,
+    pub color: Color,
+    built: bool,
+}
+
+impl DropText {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            size: 80.,
+            spacing: 11.,
+            color: Color::CYAN,
+            built: false,
+        }
+    }
+
+    pub fn with_size(self, size: f32, spacing: f32) -> Self {
+        Self {
+            size,
+            spacing,
+            ..self
+        }
+    }
+
+    pub fn with_color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+}
+
+#[derive(Component)]
+pub struct TextDrop;
+
+/// Makes the drops of a [`DropText`] fly away from its center. The text is despawned once they
+/// are gone.
+#[derive(Component)]
+pub struct Splash;
+
+/// "GAME OVER" or "STAGE CLEAR" shown during `GameState::GameOver`.
+#[derive(Component)]
+struct ResultText;
+
+/// Coverage from 0 to 1 of each pixel, row by row from the top.
+struct DropMask {
+    width: usize,
+    height: usize,
+    coverage: Vec<f32>,
+}
+
+impl DropMask {
+    fn from_text(font: &FontArc, text: &str, size: f32) -> Self {
+        let scale = PxScale::from(size);
+        let scaled = font.as_scaled(scale);
+
+        let mut glyphs = Vec::new();
+        let mut caret = 0.;
+        let mut last = None;
+        for c in text.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(last) = last {
+                caret += scaled.kern(last, id);
+            }
+            glyphs.push(id.with_scale_and_position(scale, point(caret, scaled.ascent())));
+            caret += scaled.h_advance(id);
+            last = Some(id);
+        }
+
+        let width = caret.ceil() as usize;
+        let height = (scaled.ascent() - scaled.descent()).ceil() as usize;
+        let mut coverage = vec![0f32; width * height];
+        for glyph in glyphs {
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|x, y, v| {
+                let x = bounds.min.x as i32 + x as i32;
+                let y = bounds.min.y as i32 + y as i32;
+                if (0..width as i32).contains(&x) && (0..height as i32).contains(&y) {
+                    let i = y as usize * width + x as usize;
+                    coverage[i] = coverage[i].max(v);
+                }
+            });
+        }
+
+        Self {
+            width,
+            height,
+            coverage,
+        }
+    }
+
+    fn coverage(&self, x: f32, y: f32) -> f32 {
+        if x < 0. || y < 0. {
+            return 0.;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if x >= self.width || y >= self.height {
+            return 0.;
+        }
+        self.coverage[y * self.width + x]
+    }
+
+    /// Evenly spaced positions covered by the mask, centered on the origin. Every other row is
+    /// shifted by half the spacing so the drops don't line up in columns.
+    fn sample(&self, spacing: f32) -> Vec<Vec2> {
+        let center = Vec2::new(self.width as f32, self.height as f32) / 2.;
+
+        let mut positions = Vec::new();
+        let mut row = 0;
+        let mut y = spacing / 2.;
+        while y < self.height as f32 {
+            let mut x = if row % 2 == 0 {
+                spacing / 4.
+            } else {
+                spacing * 3. / 4.
+            };
+            while x < self.width as f32 {
+                if self.coverage(x, y) > COVERAGE_THRESHOLD {
+                    positions.push(Vec2::new(x - center.x, center.y - y));
+                }
+                x += spacing;
+            }
+            y += spacing;
+            row += 1;
+        }
+        positions
+    }
+}
+
+fn startup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    commands.insert_resource(DropTextAssets {
+        mesh: Mesh2dHandle(
+            meshes.add(
+                shape::Circle {
+                    radius: DROP_RADIUS,
+                    vertices: 6,
+                }
+                .into(),
+            ),
+        ),
+    });
+}
+
+fn build_drop_text(
+    mut commands: Commands,
+    mut texts: Query<(Entity, &mut DropText)>,
+    assets: Res<DropTextAssets>,
+    fonts: Res<Assets<Font>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (id, mut text) in &mut texts {
+        if text.built {
+            continue;
+        }
+
+        // The default font is added to the assets when the app is built.
+        let Some(font) = fonts.get(Handle::<Font>::default()) else {
+            continue;
+        };
+        let mask = DropMask::from_text(&font.font, &text.text, text.size);
+        text.built = true;
+
+        let material = color_materials.add(ColorMaterial::from(text.color));
+        commands.entity(id).with_children(|c| {
+            for pos in mask.sample(text.spacing) {
+                c.spawn((
+                    TextDrop,
+                    ColorMesh2dBundle {
+                        mesh: assets.mesh.clone(),
+                        material: material.clone(),
+                        transform: Transform::from_translation(pos.extend(0.)),
+                        ..default()
+                    },
+                ));
+            }
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn splash_drops(
+    mut commands: Commands,
+    splashing: Query<(Entity, Option<&Children>), (With<DropText>, With<Splash>)>,
+    mut drops: Query<&mut Transform, With<TextDrop>>,
+    time: Res<Time>,
+) {
+    for (text, children) in &splashing {
+        let Some(children) = children.filter(|c| !c.is_empty()) else {
+            commands.entity(text).despawn_recursive();
+            continue;
+        };
+
+        for &drop in children {
+            let Ok(mut tf) = drops.get_mut(drop) else {
+                continue;
+            };
+            let direction = tf.translation.truncate().try_normalize().unwrap_or(Vec2::Y);
+            tf.translation += (direction * time.delta_seconds() * SPLASH_SPEED).extend(0.);
+
+            if tf.translation.length() > SPLASH_DISTANCE {
+                commands.entity(drop).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn spawn_result_text(
+    mut commands: Commands,
+    camera: Query<Entity, With<MainCamera>>,
+    value: &str,
+    color: Color,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    let text = commands
+        .spawn((
+            Name::new(value.to_string()),
+            ResultText,
+            DropText::new(value).with_size(64., 9.).with_color(color),
+            // In front of the level, which is drawn around z = 0.
+            SpatialBundle::from_transform(Transform::from_xyz(0., 60., -990.)),
+        ))
+        .id();
+    commands.entity(camera).add_child(text);
+}
@@ -0,0 +1,386 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use crate::{
+    boss::BOSS_SIZE,
+    enemy::{EnemyKind, ENEMY_SIZE},
+    level::{EnemyPlacement, LevelData, StageStart, StartPosition},
+    state_scoped::StateScoped,
+    title::GameMode,
+    GameState, MainCamera, MouseWorldPos, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+/// Camera speed in pixels per second when scrolling with the keys.
+const SCROLL_SPEED: f32 = 600.0;
+
+/// Pixels scrolled per line of the mouse wheel.
+const WHEEL_LINE: f32 = 40.0;
+
+const WAYPOINT_RADIUS: f32 = 8.0;
+
+const HELP: &str = "Wheel/Up/Down: scroll   Home/End: top/boss\n\
+    Drag: move enemy or waypoint   N: new enemy   I: insert waypoint\n\
+    K: switch kind   Delete: remove   B: place boss\n\
+    Ctrl+S: save   P: save and playtest from here   Esc: title";
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Editor>()
+            .add_systems(OnEnter(GameState::Editor), enter_editor)
+            .add_systems(OnExit(GameState::Editor), exit_editor)
+            .add_systems(
+                Update,
+                (
+                    scroll_editor,
+                    edit_level,
+                    editor_keys,
+                    draw_level,
+                    update_status,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Editor)),
+            );
+    }
+}
+
+/// Set while the level is played from the editor, so that the game returns to it.
+#[derive(Resource)]
+pub struct Playtest;
+
+#[derive(Resource, Default)]
+struct Editor {
+    camera_y: f32,
+    selected: Option<Selection>,
+    dragging: bool,
+    /// Kind of the enemies placed with N.
+    kind: EnemyKind,
+    status: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Selection {
+    Enemy(usize),
+    Waypoint(usize, usize),
+    Boss,
+}
+
+#[derive(Component)]
+struct EditorStatus;
+
+fn enter_editor(
+    mut commands: Commands,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+    editor: Res<Editor>,
+) {
+    // Back from a playtest, if there was one.
+    commands.remove_resource::<Playtest>();
+    camera.single_mut().translation.y = editor.camera_y;
+
+    let text_style = TextStyle {
+        font_size: 16.,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands
+        .spawn((
+            Name::new("Editor UI"),
+            StateScoped(GameState::Editor),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(8.),
+                    top: Val::Px(8.),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.),
+                    ..default()
+                },
+                background_color: Color::BLACK.with_a(0.5).into(),
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn(TextBundle::from_section(HELP, text_style.clone()));
+            c.spawn((EditorStatus, TextBundle::from_section("", text_style)));
+        });
+}
+
+fn exit_editor(
+    mut editor: ResMut<Editor>,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+    playtest: Option<Res<Playtest>>,
+) {
+    editor.dragging = false;
+    // A playtest places the camera at its start, the title expects it at the top.
+    if playtest.is_none() {
+        camera.single_mut().translation.y = 0.;
+    }
+}
+
+fn scroll_editor(
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+    mut editor: ResMut<Editor>,
+    mut wheel: EventReader<MouseWheel>,
+    keys: Res<Input<KeyCode>>,
+    level: Res<LevelData>,
+    time: Res<Time<Real>>,
+) {
+    let mut dy = 0.;
+    for ev in wheel.read() {
+        dy += match ev.unit {
+            MouseScrollUnit::Line => ev.y * WHEEL_LINE,
+            MouseScrollUnit::Pixel => ev.y,
+        };
+    }
+    // Ctrl+S saves instead.
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        if keys.any_pressed([KeyCode::Up, KeyCode::W]) {
+            dy += SCROLL_SPEED * time.delta_seconds();
+        }
+        if keys.any_pressed([KeyCode::Down, KeyCode::S]) {
+            dy -= SCROLL_SPEED * time.delta_seconds();
+        }
+    }
+    if dy != 0. {
+        editor.camera_y += dy;
+    }
+
+    if keys.just_pressed(KeyCode::Home) {
+        editor.camera_y = 0.;
+    }
+    if keys.just_pressed(KeyCode::End) {
+        if let Some(y) = level.boss_camera_y() {
+            editor.camera_y = y;
+        }
+    }
+
+    let mut camera = camera.single_mut();
+    if camera.translation.y != editor.camera_y {
+        camera.translation.y = editor.camera_y;
+    }
+}
+
+/// The handle under the cursor. Waypoints are picked before the bigger enemies and boss.
+fn pick(level: &LevelData, pos: Vec2) -> Option<Selection> {
+    for (i, enemy) in level.enemies.iter().enumerate() {
+        for (j, waypoint) in enemy.waypoints.iter().enumerate() {
+            if waypoint.distance(pos) <= WAYPOINT_RADIUS {
+                return Some(Selection::Waypoint(i, j));
+            }
+        }
+    }
+    for (i, enemy) in level.enemies.iter().enumerate() {
+        if (enemy.pos - pos).abs().max_element() <= ENEMY_SIZE / 2. {
+            return Some(Selection::Enemy(i));
+        }
+    }
+    level
+        .boss_pos
+        .filter(|boss_pos| boss_pos.distance(pos) <= BOSS_SIZE / 2.)
+        .map(|_| Selection::Boss)
+}
+
+fn edit_level(
+    mut editor: ResMut<Editor>,
+    mut level: ResMut<LevelData>,
+    buttons: Res<Input<MouseButton>>,
+    mouse_pos: Option<Res<MouseWorldPos>>,
+    ui: Query<&Interaction>,
+) {
+    let Some(mouse_pos) = mouse_pos.map(|p| p.0) else {
+        return;
+    };
+
+    if buttons.just_pressed(MouseButton::Left) && ui.iter().all(|i| *i == Interaction::None) {
+        editor.selected = pick(&level, mouse_pos);
+        editor.dragging = editor.selected.is_some();
+    }
+    if buttons.just_released(MouseButton::Left) {
+        editor.dragging = false;
+    }
+
+    if !editor.dragging {
+        return;
+    }
+    match editor.selected {
+        Some(Selection::Enemy(i)) => level.enemies[i].pos = mouse_pos,
+        Some(Selection::Waypoint(i, j)) => level.enemies[i].waypoints[j] = mouse_pos,
+        Some(Selection::Boss) => level.boss_pos = Some(mouse_pos),
+        None => {}
+    }
+}
+
+fn editor_keys(
+    mut commands: Commands,
+    mut editor: ResMut<Editor>,
+    mut level: ResMut<LevelData>,
+    keys: Res<Input<KeyCode>>,
+    mouse_pos: Option<Res<MouseWorldPos>>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    let mouse_pos = mouse_pos.map_or(Vec2::new(0., editor.camera_y), |p| p.0);
+
+    if keys.just_pressed(KeyCode::N) {
+        // Enters at the cursor and leaves to the right.
+        let stop = mouse_pos - Vec2::Y * 150.;
+        level.enemies.push(EnemyPlacement {
+            pos: mouse_pos,
+            waypoints: vec![stop, Vec2::new(SCREEN_WIDTH / 2. + ENEMY_SIZE / 2., stop.y)],
+            kind: editor.kind,
+        });
+        editor.selected = Some(Selection::Enemy(level.enemies.len() - 1));
+    }
+
+    if keys.just_pressed(KeyCode::I) {
+        // Halfway to the next point, or below the last one.
+        let inserted = match editor.selected {
+            Some(Selection::Enemy(i)) => {
+                let enemy = &level.enemies[i];
+                let next = enemy.waypoints.first().copied();
+                Some((i, 0, enemy.pos, next))
+            }
+            Some(Selection::Waypoint(i, j)) => {
+                let enemy = &level.enemies[i];
+                let next = enemy.waypoints.get(j + 1).copied();
+                Some((i, j + 1, enemy.waypoints[j], next))
+            }
+            _ => None,
+        };
+        if let Some((i, j, from, next)) = inserted {
+            let pos = next.map_or(from - Vec2::Y * 100., |next| (from + next) / 2.);
+            level.enemies[i].waypoints.insert(j, pos);
+            editor.selected = Some(Selection::Waypoint(i, j));
+        }
+    }
+
+    if keys.just_pressed(KeyCode::K) {
+        let next_kind = |kind| match kind {
+            EnemyKind::Normal => EnemyKind::Ice,
+            EnemyKind::Ice => EnemyKind::Normal,
+        };
+        match editor.selected {
+            Some(Selection::Enemy(i) | Selection::Waypoint(i, _)) => {
+                let kind = next_kind(level.enemies[i].kind);
+                level.enemies[i].kind = kind;
+                editor.kind = kind;
+            }
+            _ => editor.kind = next_kind(editor.kind),
+        }
+    }
+
+    if keys.any_just_pressed([KeyCode::Delete, KeyCode::Back]) {
+        match editor.selected.take() {
+            // Every enemy keeps at least one waypoint to trigger it.
+            Some(Selection::Waypoint(i, j)) if level.enemies[i].waypoints.len() > 1 => {
+                level.enemies[i].waypoints.remove(j);
+            }
+            Some(Selection::Enemy(i) | Selection::Waypoint(i, _)) => {
+                level.enemies.remove(i);
+            }
+            Some(Selection::Boss) => level.boss_pos = None,
+            None => {}
+        }
+        editor.dragging = false;
+    }
+
+    if keys.just_pressed(KeyCode::B) {
+        level.boss_pos = Some(mouse_pos);
+        editor.selected = Some(Selection::Boss);
+    }
+
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if ctrl && keys.just_pressed(KeyCode::S) {
+        editor.status = if level.save() {
+            "Saved".to_string()
+        } else {
+            "Saving failed".to_string()
+        };
+    }
+
+    if keys.just_pressed(KeyCode::P) {
+        level.save();
+        commands.insert_resource(Playtest);
+        // Edited levels don't count for the high scores.
+        commands.insert_resource(GameMode::Practice);
+        commands.insert_resource(StageStart {
+            position: StartPosition::CameraY(editor.camera_y),
+            player_radius: None,
+        });
+        state.set(GameState::Main);
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        state.set(GameState::Title);
+    }
+}
+
+fn draw_level(mut gizmos: Gizmos, level: Res<LevelData>, editor: Res<Editor>) {
+    let highlight = |selection: Selection, color: Color| {
+        if editor.selected == Some(selection) {
+            Color::YELLOW
+        } else {
+            color
+        }
+    };
+
+    for (i, enemy) in level.enemies.iter().enumerate() {
+        let color = enemy.kind.color();
+        gizmos.rect_2d(
+            enemy.pos,
+            0.,
+            Vec2::splat(ENEMY_SIZE),
+            highlight(Selection::Enemy(i), color),
+        );
+        gizmos.linestrip_2d(
+            std::iter::once(enemy.pos).chain(enemy.waypoints.iter().copied()),
+            color.with_a(0.5),
+        );
+        for (j, &waypoint) in enemy.waypoints.iter().enumerate() {
+            // The first waypoint triggers the spawn.
+            let color = if j == 0 { Color::GREEN } else { color };
+            gizmos.circle_2d(
+                waypoint,
+                WAYPOINT_RADIUS,
+                highlight(Selection::Waypoint(i, j), color),
+            );
+        }
+    }
+
+    if let Some(boss_pos) = level.boss_pos {
+        gizmos.circle_2d(
+            boss_pos,
+            BOSS_SIZE / 2.,
+            highlight(Selection::Boss, Color::RED),
+        );
+    }
+
+    // The screen at the playtest start
+    gizmos.rect_2d(
+        Vec2::new(0., editor.camera_y),
+        0.,
+        Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT) - 4.,
+        Color::WHITE.with_a(0.3),
+    );
+}
+
+fn update_status(
+    editor: Res<Editor>,
+    level: Res<LevelData>,
+    mut text: Query<&mut Text, With<EditorStatus>>,
+) {
+    if !editor.is_changed() && !level.is_changed() {
+        return;
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!(
+            "y: {:.0}   enemies: {}   kind: {:?}   {}",
+            editor.camera_y,
+            level.enemies.len(),
+            editor.kind,
+            editor.status
+        );
+    }
+}
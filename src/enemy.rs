@@ -1,11 +1,23 @@
-use bevy::{
-    audio::{Volume, VolumeLevel},
-    prelude::*,
-};
+use bevy::prelude::*;
+#[cfg(feature = "dev")]
 use bevy_debug_text_overlay::screen_print;
 use bevy_xpbd_2d::prelude::*;
-
-use crate::{health::Health, MainCamera, MyLayer, SCREEN_WIDTH};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::{Sound, SoundEvent},
+    bullet_collision::{BulletCollider, Hurtbox, HurtboxShape, Team},
+    bullet_pool::{BulletKind, BulletPool, PooledBullet},
+    culling::Cull,
+    damage::{Damage, Resistances, WeakPoint, ENEMY_BULLET_DAMAGE},
+    difficulty::Difficulty,
+    health::{DamageKind, DespawnOnDeath, Health},
+    item::DropItems,
+    particle::{ExplodeOnDeath, ParticleEmitter},
+    score::{ScoreValue, ENEMY_SCORE},
+    state_scoped::StateScoped,
+    GameState, MyLayer, SCREEN_WIDTH,
+};
 
 fn startup(mut commands: Commands) {
     commands.init_resource::<EnemyResource>();
@@ -20,6 +32,7 @@ pub const ENEMY_SIZE: f32 = 80.0;
 pub struct EnemyController {
     state: EnemyState,
     pub attack_pos: Vec<Vec2>,
+    pub kind: EnemyKind,
 }
 
 impl From<Vec<Vec2>> for EnemyController {
@@ -27,6 +40,40 @@ impl From<Vec<Vec2>> for EnemyController {
         Self {
             attack_pos: value,
             state: EnemyState::default(),
+            kind: EnemyKind::default(),
+        }
+    }
+}
+
+impl EnemyController {
+    pub fn with_kind(mut self, kind: EnemyKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub enum EnemyKind {
+    #[default]
+    Normal,
+    /// Frozen enemy resisting water shots.
+    Ice,
+}
+
+impl EnemyKind {
+    fn resistances(self) -> Resistances {
+        match self {
+            EnemyKind::Normal => Resistances::default(),
+            EnemyKind::Ice => Resistances::default()
+                .with(DamageKind::Water, 0.5)
+                .with(DamageKind::Charged, 1.5),
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            EnemyKind::Normal => Color::WHITE,
+            EnemyKind::Ice => Color::rgb(0.6, 0.8, 1.0),
         }
     }
 }
@@ -36,8 +83,14 @@ pub enum EnemyState {
     Attacking,
     #[default]
     Moving,
+    /// Done with its path, keeps moving in the given direction until it is culled off-screen.
+    Leaving(Vec2),
 }
 
+/// Sent when an enemy leaves the screen without being killed.
+#[derive(Event)]
+pub struct EnemyEscapedEvent;
+
 #[derive(Bundle)]
 pub struct EnemyBundle {
     pub enemy: Enemy,
@@ -60,11 +113,13 @@ pub fn spawn_enemy(commands: &mut Commands, pos: Vec2, controller: EnemyControll
     ];
     attack_pos.reverse();
 
+    let resistances = controller.kind.resistances();
     commands
         .spawn(EnemyBundle {
             sprite: SpriteBundle {
                 transform: Transform::from_translation(pos.extend(0.0)),
                 sprite: Sprite {
+                    color: controller.kind.color(),
                     custom_size: Some(Vec2::new(ENEMY_SIZE, ENEMY_SIZE)),
                     ..default()
                 },
@@ -81,31 +136,47 @@ pub fn spawn_enemy(commands: &mut Commands, pos: Vec2, controller: EnemyControll
         .insert((
             Collider::ball(ENEMY_SIZE / 2.0),
             RigidBody::Kinematic,
-            CollisionLayers::new([MyLayer::Enemy], [MyLayer::Player, MyLayer::PlayerBullet]),
+            CollisionLayers::new([MyLayer::Enemy], [MyLayer::Player]),
+            Hurtbox {
+                shape: HurtboxShape::Circle(ENEMY_SIZE / 2.0),
+                team: Team::Enemy,
+            },
+        ))
+        .insert((
+            DropItems {
+                count: 32,
+                spread: 50.,
+            },
+            ScoreValue(ENEMY_SCORE),
+            DespawnOnDeath,
+            ExplodeOnDeath(ParticleEmitter::explosion()),
+            StateScoped(GameState::Main),
+        ))
+        .insert((
+            resistances,
+            WeakPoint {
+                offset: Vec2::ZERO,
+                radius: ENEMY_SIZE / 6.,
+                multiplier: 1.5,
+            },
         ));
 }
 
 #[derive(Resource)]
 pub struct EnemyResource {
     image: Handle<Image>,
-    pub bullet_sound: Handle<AudioSource>,
+    bullet_image: Handle<Image>,
 }
 
 impl FromWorld for EnemyResource {
     fn from_world(world: &mut World) -> Self {
-        let image = world
-            .get_resource::<AssetServer>()
-            .unwrap()
-            .load::<Image>("sprites/snow.png");
-
-        let bullet_sound = world
-            .get_resource::<AssetServer>()
-            .unwrap()
-            .load("sounds/ice.ogg");
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        let image = asset_server.load::<Image>("sprites/snow.png");
+        let bullet_image = asset_server.load::<Image>("sprites/spiral.png");
 
         Self {
             image,
-            bullet_sound,
+            bullet_image,
         }
     }
 }
@@ -124,51 +195,66 @@ impl EnemyBundle {
 fn enemy_state_behavior(
     mut commands: Commands,
     mut enemies: Query<(Entity, &EnemyController), Changed<EnemyController>>,
+    difficulty: Res<Difficulty>,
 ) {
     for (entity, ctrl) in &mut enemies {
         // screen_print!("Enemy({:?}) ctrl: {:?}", entity, ctrl);
 
         match ctrl.state {
             EnemyState::Attacking => {
-                commands.entity(entity).try_insert(LineUpBullets::default());
+                commands.entity(entity).try_insert(LineUpBullets {
+                    num: difficulty.bullet_count(16),
+                    ..default()
+                });
             }
             EnemyState::Moving => {
                 commands.entity(entity).remove::<LineUpBullets>();
             }
+            EnemyState::Leaving(_) => {
+                commands.entity(entity).insert(Cull {
+                    margin: ENEMY_SIZE / 2.,
+                });
+            }
         }
     }
 }
 
 fn enemy_movement(
-    mut commands: Commands,
-    mut enemies: Query<(Entity, &mut Transform, &mut EnemyController)>,
+    mut enemies: Query<(&mut Transform, &mut EnemyController)>,
     time: Res<Time<Virtual>>,
 ) {
-    for (id, mut transform, mut ctrl) in &mut enemies {
-        if ctrl.state != EnemyState::Moving {
-            continue;
-        }
-
-        let Some(move_target) = ctrl.attack_pos.last() else {
-            commands.entity(id).despawn_recursive();
-            continue;
+    for (mut transform, mut ctrl) in &mut enemies {
+        let move_target = match ctrl.state {
+            EnemyState::Attacking => continue,
+            EnemyState::Moving => match ctrl.attack_pos.last() {
+                Some(target) => *target,
+                None => {
+                    ctrl.state = EnemyState::Leaving(Vec2::X);
+                    continue;
+                }
+            },
+            EnemyState::Leaving(direction) => {
+                transform.translation += (direction * 100.0 * time.delta_seconds()).extend(0.0);
+                continue;
+            }
         };
 
         // // screen_print!("movement_target: {}", move_target);
 
-        let diff = *move_target - transform.translation.xy();
+        let diff = move_target - transform.translation.xy();
         let movement = diff.normalize() * 100.0 * time.delta_seconds();
         // // screen_print!("diff = {}, movement = {}", diff, movement);
 
         // If the enemy is close to the target, just move it to the target.
         if diff.length() < 0.1
-            || (*move_target - transform.translation.xy()).length() <= movement.length()
+            || (move_target - transform.translation.xy()).length() <= movement.length()
         {
             transform.translation = move_target.extend(transform.translation.z);
 
             ctrl.attack_pos.pop();
             if ctrl.attack_pos.len() == 0 {
-                commands.entity(id).despawn_recursive();
+                // Keep going the way it came, it is culled once off-screen.
+                ctrl.state = EnemyState::Leaving(diff.try_normalize().unwrap_or(Vec2::X));
             } else {
                 ctrl.state = EnemyState::Attacking;
             }
@@ -192,29 +278,23 @@ fn enemy_attack_done(
 #[derive(Component)]
 pub struct EnemyBullet;
 
-fn rotate_bullets(time: Res<Time<Virtual>>, mut bullets: Query<&mut Transform, With<EnemyBullet>>) {
-    for mut transform in &mut bullets {
-        transform.rotate(Quat::from_rotation_z(time.delta_seconds() * 2.0));
-    }
-}
-
-fn despawn_enemy_bullet(
-    mut commands: Commands,
-    bullets: Query<(Entity, &Transform), With<EnemyBullet>>,
-    camera: Query<&Transform, With<MainCamera>>,
+fn rotate_bullets(
+    time: Res<Time<Virtual>>,
+    mut bullets: Query<(&mut Transform, &PooledBullet), With<EnemyBullet>>,
 ) {
-    let camera_transform = camera.single();
-    for (id, transform) in &bullets {
-        if (transform.translation - camera_transform.translation).length() > 1000.0 {
-            commands.entity(id).despawn_recursive();
+    for (mut transform, pooled) in &mut bullets {
+        if !pooled.is_active() {
+            continue;
         }
+        transform.rotate(Quat::from_rotation_z(time.delta_seconds() * 2.0));
     }
 }
 
 #[derive(Component)]
 pub struct LineUpBullets {
     num: u32,
-    bullets: Vec<(Entity, Vec3)>,
+    /// Bullets lined up so far.
+    count: u32,
     next_timer: Timer,
     angle: f32,
     done: bool,
@@ -224,7 +304,7 @@ impl Default for LineUpBullets {
     fn default() -> Self {
         Self {
             num: 16,
-            bullets: Vec::new(),
+            count: 0,
             next_timer: Timer::from_seconds(0.05, TimerMode::Repeating),
             angle: 0.0,
             done: false,
@@ -234,85 +314,108 @@ impl Default for LineUpBullets {
 
 const BULLET_SIZE: f32 = 32.0;
 
+/// Enemy bullet waiting next to the enemy lining it up, until the enemy fires the whole line.
+/// Released bullets keep it, the enemy of the bullet's next use replaces it.
 #[derive(Component)]
-struct StillBullet;
+pub struct StillBullet(pub Option<Entity>);
 
-fn spawn_still_bullet(
-    mut commands: Commands,
-    enemies: Query<(Entity, &Transform), Added<StillBullet>>,
-    assets: Res<AssetServer>,
+#[derive(Component)]
+pub struct StraightBullet(pub Vec3);
+
+/// Spawns an enemy bullet flying at `velocity`, once `lined_up_by` fires it if given.
+pub fn spawn_enemy_bullet(
+    commands: &mut Commands,
+    pool: &mut BulletPool,
+    res: &EnemyResource,
+    pos: Vec3,
+    velocity: Vec3,
+    lined_up_by: Option<Entity>,
 ) {
-    for (entity, transform) in &enemies {
-        commands
-            .entity(entity)
-            .insert(SpriteBundle {
-                texture: assets.load("sprites/spiral.png"),
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(BULLET_SIZE, BULLET_SIZE)),
-                    ..default()
-                },
-                transform: *transform,
+    pool.acquire(commands, BulletKind::Enemy).insert((
+        SpriteBundle {
+            texture: res.bullet_image.clone(),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(BULLET_SIZE, BULLET_SIZE)),
                 ..default()
-            })
-            .insert(EnemyBullet)
-            .insert(Name::new("EnemyBullet"))
-            .insert((
-                Collider::ball(BULLET_SIZE / 2. * 0.6),
-                RigidBody::Kinematic,
-                CollisionLayers::new([MyLayer::EnemyBullet], [MyLayer::Player]),
-            ));
-    }
+            },
+            transform: Transform::from_translation(pos),
+            ..default()
+        },
+        EnemyBullet,
+        Name::new("EnemyBullet"),
+        BulletCollider::new(BULLET_SIZE / 2. * 0.6, Team::Enemy),
+        Cull {
+            margin: BULLET_SIZE / 2.,
+        },
+        Damage {
+            amount: ENEMY_BULLET_DAMAGE,
+            kind: DamageKind::Ice,
+        },
+        StraightBullet(velocity),
+        StillBullet(lined_up_by),
+    ));
 }
 
-#[derive(Component)]
-pub struct StraightBullet(pub Vec3);
-
 fn line_up_bullets_system(
     mut commands: Commands,
-    mut q: Query<(&mut LineUpBullets, &Transform)>,
+    mut pool: ResMut<BulletPool>,
+    mut q: Query<(Entity, &mut LineUpBullets, &Transform)>,
+    mut still_bullets: Query<&mut StillBullet>,
+    res: Res<EnemyResource>,
     time: Res<Time<Virtual>>,
-    enemy_res: Res<EnemyResource>,
+    mut sounds: EventWriter<SoundEvent>,
 ) {
-    for (mut line_up_bullets, transform) in &mut q {
+    let mut fired = Vec::new();
+    for (id, mut line_up_bullets, transform) in &mut q {
         if line_up_bullets.next_timer.tick(time.delta()).finished() {
-            if line_up_bullets.bullets.len() >= line_up_bullets.num as usize {
-                for &(entity, delta) in &line_up_bullets.bullets {
-                    commands.add(move |world: &mut World| {
-                        if let Some(mut e) = world.get_entity_mut(entity) {
-                            e.remove::<StillBullet>().insert(StraightBullet(delta));
-                        }
-                    });
-                }
+            if line_up_bullets.count >= line_up_bullets.num {
+                fired.push(id);
                 line_up_bullets.done = true;
                 continue;
             }
 
             let angle = line_up_bullets.angle;
             let delta = Vec3::new(-angle.sin(), angle.cos(), 0.0) * ENEMY_SIZE / 2.;
-            let id = commands
-                .spawn(StillBullet)
-                .insert(Transform::from_translation(transform.translation + delta))
-                .insert(AudioBundle {
-                    source: enemy_res.bullet_sound.clone(),
-                    settings: PlaybackSettings {
-                        mode: bevy::audio::PlaybackMode::Once,
-                        volume: Volume::Relative(VolumeLevel::new(0.2)),
-                        ..default()
-                    },
-                })
-                .id();
-            line_up_bullets.bullets.push((id, delta));
+            spawn_enemy_bullet(
+                &mut commands,
+                &mut pool,
+                &res,
+                transform.translation + delta,
+                delta,
+                Some(id),
+            );
+            sounds.send(SoundEvent(Sound::EnemyShot));
+            line_up_bullets.count += 1;
             line_up_bullets.angle += 2.0 * std::f32::consts::PI / line_up_bullets.num as f32;
         }
     }
+
+    if fired.is_empty() {
+        return;
+    }
+    for mut still in &mut still_bullets {
+        if still.0.is_some_and(|enemy| fired.contains(&enemy)) {
+            still.0 = None;
+        }
+    }
 }
 
 fn move_straight_bullet(
     time: Res<Time<Virtual>>,
-    mut bullets: Query<(&mut Transform, &StraightBullet)>,
+    mut bullets: Query<(
+        &mut Transform,
+        &StraightBullet,
+        &PooledBullet,
+        Option<&StillBullet>,
+    )>,
+    difficulty: Res<Difficulty>,
 ) {
-    for (mut transform, StraightBullet(delta)) in &mut bullets {
-        transform.translation += *delta * time.delta_seconds();
+    let speed = difficulty.bullet_speed();
+    for (mut transform, StraightBullet(delta), pooled, still) in &mut bullets {
+        if !pooled.is_active() || still.is_some_and(|still| still.0.is_some()) {
+            continue;
+        }
+        transform.translation += *delta * speed * time.delta_seconds();
     }
 }
 
@@ -320,15 +423,14 @@ pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, startup);
+        app.add_event::<EnemyEscapedEvent>()
+            .add_systems(Startup, startup);
         app.add_systems(PostUpdate, EnemyBundle::set_handles);
         // .add_systems(Update, spawn_enemy_bullet)
         app.add_systems(Update, rotate_bullets);
 
         app.add_systems(Update, line_up_bullets_system)
-            .add_systems(Update, spawn_still_bullet)
-            .add_systems(Update, move_straight_bullet)
-            .add_systems(Update, despawn_enemy_bullet);
+            .add_systems(Update, move_straight_bullet);
 
         app.add_systems(
             Update,
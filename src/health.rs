@@ -1,9 +1,10 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, render::primitives::Aabb, sprite::Anchor, transform::TransformSystem};
+#[cfg(feature = "dev")]
 use bevy_debug_text_overlay::screen_print;
 
 const HEALTH_BAR_Z: f32 = 100.0;
-const HEALTH_BAR_HEIGHT: f32 = 5.0;
-const HEALTH_BAR_OFFSET: f32 = 15.0;
+const HEALTH_BAR_MARGIN: f32 = 10.0;
+const SEGMENT_GAP: f32 = 1.0;
 
 #[derive(Component, Debug)]
 pub struct Health {
@@ -17,84 +18,338 @@ impl Health {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DamageKind {
+    #[default]
+    Normal,
+    /// Regular player shots.
+    Water,
+    /// Charged player shots.
+    Charged,
+    /// Enemy bullets.
+    Ice,
+}
+
+/// Requests applying damage to an entity with [`Health`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    /// The entity that dealt the damage, e.g. a bullet.
+    pub source: Option<Entity>,
+    pub kind: DamageKind,
+    /// Whether the damage was a critical hit.
+    pub critical: bool,
+    /// Where the hit landed.
+    pub position: Option<Vec2>,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HealthChanged {
+    pub entity: Entity,
+    pub old: f32,
+    pub new: f32,
+}
+
+/// Sent once when an entity runs out of health (or radius, for the player).
+/// Entities react to it in [`HealthSet::Death`] through their components.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Died {
+    pub entity: Entity,
+    /// Source of the killing [`DamageEvent`].
+    pub killer: Option<Entity>,
+}
+
+/// Despawns the entity when it dies.
 #[derive(Component)]
-pub struct HealthBar;
+pub struct DespawnOnDeath;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HealthSet {
+    /// Applies [`DamageEvent`]s.
+    Damage,
+    /// Reactions to [`Died`].
+    Death,
+    /// Despawns dead entities after all reactions ran.
+    Despawn,
+}
+
+fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut healths: Query<&mut Health>,
+    mut changed_events: EventWriter<HealthChanged>,
+    mut died_events: EventWriter<Died>,
+) {
+    for ev in damage_events.read() {
+        let Ok(mut health) = healths.get_mut(ev.target) else {
+            continue;
+        };
+        if health.health <= 0. {
+            continue;
+        }
+
+        let old = health.health;
+        health.health = (health.health - ev.amount).max(0.);
+        changed_events.send(HealthChanged {
+            entity: ev.target,
+            old,
+            new: health.health,
+        });
+
+        if health.health <= 0. {
+            died_events.send(Died {
+                entity: ev.target,
+                killer: ev.source,
+            });
+        }
+    }
+}
+
+fn despawn_on_death(
+    mut commands: Commands,
+    mut died_events: EventReader<Died>,
+    q: Query<(), With<DespawnOnDeath>>,
+) {
+    for ev in died_events.read() {
+        if q.contains(ev.entity) {
+            commands.entity(ev.entity).despawn_recursive();
+        }
+    }
+}
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<HealthChanged>()
+            .add_event::<Died>()
+            .configure_sets(
+                PostUpdate,
+                (HealthSet::Damage, HealthSet::Death, HealthSet::Despawn).chain(),
+            )
+            .add_systems(PostUpdate, apply_damage.in_set(HealthSet::Damage))
+            .add_systems(PostUpdate, despawn_on_death.in_set(HealthSet::Despawn));
+    }
+}
+
+/// Appearance of the health bar of an entity with [`Health`].
+/// Entities without it get the default style.
+#[derive(Component, Clone, Debug)]
+pub struct HealthBarStyle {
+    pub width: f32,
+    pub height: f32,
+    pub foreground: Color,
+    pub background: Color,
+    /// Color of the part of the bar that was just lost.
+    pub ghost: Color,
+    /// Position relative to the entity. If `None`, the bar is placed above the entity's
+    /// sprite or mesh, including the ones on its children.
+    pub offset: Option<Vec2>,
+    pub show_when_full: bool,
+    /// Number of segments the foreground is divided into.
+    pub segments: u32,
+    /// Speed of the foreground and ghost bars draining, in fractions of the bar per second.
+    pub drain_speed: f32,
+    /// Seconds the ghost bar waits after damage before draining.
+    pub ghost_delay: f32,
+}
+
+impl Default for HealthBarStyle {
+    fn default() -> Self {
+        Self {
+            width: 100.0,
+            height: 5.0,
+            foreground: Color::BLUE.with_a(0.5),
+            background: Color::BLACK.with_a(0.5),
+            ghost: Color::WHITE.with_a(0.5),
+            offset: None,
+            show_when_full: false,
+            segments: 1,
+            drain_speed: 2.0,
+            ghost_delay: 0.5,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct HealthBar {
+    style: HealthBarStyle,
+    /// Fraction shown by the foreground.
+    shown: f32,
+    /// Fraction shown by the ghost bar.
+    ghost: f32,
+    ghost_wait: f32,
+    last_fraction: f32,
+}
+
+#[derive(Component)]
+enum HealthBarPart {
+    Background,
+    Ghost,
+    Segment(u32),
+}
 
 fn add_healthbar(
     mut commands: Commands,
-    q: Query<(Entity, Option<(&Sprite, &Transform)>), Added<Health>>,
+    q: Query<(Entity, Option<&HealthBarStyle>), Added<Health>>,
 ) {
-    for (entity, sprite_transform) in &q {
-        let y = sprite_transform
-            .and_then(|(sprite, transform)| {
-                sprite.custom_size.map(|size| {
-                    size.y * transform.scale.y - HEALTH_BAR_HEIGHT / 2. + HEALTH_BAR_OFFSET
-                })
-            })
-            .unwrap_or(0.0)
-            / 2.0;
+    for (entity, style) in &q {
+        let style = style.cloned().unwrap_or_default();
+        let left = -style.width / 2.;
+        let segment_width = segment_width(&style);
 
         commands.entity(entity).with_children(|c| {
-            c.spawn((HealthBar, SpatialBundle::HIDDEN_IDENTITY))
-                .with_children(|c| {
-                    // Foreground
-                    c.spawn(SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::BLUE.with_a(0.5),
-                            custom_size: Some(Vec2::new(100.0, HEALTH_BAR_HEIGHT)),
-                            ..default()
-                        },
-                        transform: Transform::from_xyz(0., y, HEALTH_BAR_Z),
-                        ..default()
-                    });
-                    // Background
-                    c.spawn(SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::BLACK.with_a(0.5),
-                            custom_size: Some(Vec2::new(100.0, HEALTH_BAR_HEIGHT)),
-                            ..default()
-                        },
-                        transform: Transform::from_xyz(0., y, HEALTH_BAR_Z - 1.0),
+            c.spawn((
+                HealthBar {
+                    style: style.clone(),
+                    shown: 1.0,
+                    ghost: 1.0,
+                    ghost_wait: 0.0,
+                    last_fraction: 1.0,
+                },
+                SpatialBundle::HIDDEN_IDENTITY,
+            ))
+            .with_children(|c| {
+                let part = |color: Color, x: f32, z: f32, width: f32| SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(width, style.height)),
+                        anchor: Anchor::CenterLeft,
                         ..default()
-                    });
-                });
+                    },
+                    transform: Transform::from_xyz(x, 0., z),
+                    ..default()
+                };
+
+                c.spawn((
+                    HealthBarPart::Background,
+                    part(style.background, left, 0.0, style.width),
+                ));
+                c.spawn((
+                    HealthBarPart::Ghost,
+                    part(style.ghost, left, 0.1, style.width),
+                ));
+                for i in 0..style.segments {
+                    let x = left + i as f32 * (segment_width + SEGMENT_GAP);
+                    c.spawn((
+                        HealthBarPart::Segment(i),
+                        part(style.foreground, x, 0.2, segment_width),
+                    ));
+                }
+            });
         });
     }
 }
 
+fn segment_width(style: &HealthBarStyle) -> f32 {
+    let segments = style.segments.max(1) as f32;
+    (style.width - SEGMENT_GAP * (segments - 1.)) / segments
+}
+
 fn update_healthbar(
-    healths: Query<(&Health, &Children), (Changed<Health>, Without<HealthBar>)>,
-    mut health_bars: Query<(&Children, &mut Visibility), (With<HealthBar>, Without<Health>)>,
-    mut transforms: Query<&mut Transform>,
+    healths: Query<&Health>,
+    mut health_bars: Query<(&mut HealthBar, &Parent, &Children, &mut Visibility)>,
+    mut parts: Query<(&mut Sprite, &HealthBarPart)>,
+    time: Res<Time>,
 ) {
-    for (health, children) in &healths {
-        // screen_print!("Health: {:?} (children = {})", health, children.len());
-
-        for child in children {
-            if let Ok((bar_children, mut vis)) = health_bars.get_mut(*child) {
-                if health.percent() >= 100.0 {
-                    vis.set_if_neq(Visibility::Hidden);
-                } else {
-                    vis.set_if_neq(Visibility::Inherited);
-                }
+    let dt = time.delta_seconds();
 
-                // screen_print!("bar_children = {:?}", bar_children);
-                let foreground = bar_children[0];
-                let mut tf = transforms.get_mut(foreground).unwrap();
-                tf.scale.x = health.percent() / 100.0;
-                tf.translation.x = -50.0 + health.percent() / 2.0;
-            }
+    for (mut bar, parent, children, mut vis) in &mut health_bars {
+        let Ok(health) = healths.get(parent.get()) else {
+            continue;
+        };
+        // screen_print!("Health: {:?}", health);
+
+        let fraction = (health.percent() / 100.0).clamp(0., 1.);
+        if fraction < bar.last_fraction {
+            bar.ghost_wait = bar.style.ghost_delay;
+        }
+        bar.last_fraction = fraction;
+
+        let drain = bar.style.drain_speed * dt;
+        bar.shown = if fraction < bar.shown {
+            (bar.shown - drain).max(fraction)
+        } else {
+            fraction
+        };
+        if bar.ghost_wait > 0. {
+            bar.ghost_wait -= dt;
+        } else {
+            bar.ghost -= drain;
+        }
+        bar.ghost = bar.ghost.max(bar.shown);
+
+        if fraction >= 1.0 && bar.ghost >= 1.0 && !bar.style.show_when_full {
+            vis.set_if_neq(Visibility::Hidden);
+        } else {
+            vis.set_if_neq(Visibility::Inherited);
+        }
+
+        let style = &bar.style;
+        let segments = style.segments.max(1) as f32;
+        let segment_width = segment_width(style);
+        for &child in children {
+            let Ok((mut sprite, part)) = parts.get_mut(child) else {
+                continue;
+            };
+            let width = match part {
+                HealthBarPart::Background => continue,
+                HealthBarPart::Ghost => style.width * bar.ghost,
+                HealthBarPart::Segment(i) => {
+                    segment_width * (bar.shown * segments - *i as f32).clamp(0., 1.)
+                }
+            };
+            sprite.custom_size = Some(Vec2::new(width, style.height));
         }
     }
 }
 
+// Keeps the bars upright above their entities.
+#[allow(clippy::type_complexity)]
+fn place_healthbar(
+    mut health_bars: Query<(&HealthBar, &Parent, &mut Transform)>,
+    parents: Query<(&Transform, Option<&Aabb>, Option<&Children>), Without<HealthBar>>,
+    child_bounds: Query<(&Transform, &Aabb), Without<HealthBar>>,
+) {
+    for (bar, parent, mut transform) in &mut health_bars {
+        let Ok((parent_transform, aabb, children)) = parents.get(parent.get()) else {
+            continue;
+        };
+
+        let offset = bar.style.offset.unwrap_or_else(|| {
+            let own_top = aabb.map(|aabb| aabb.center.y + aabb.half_extents.y);
+            let child_top = children
+                .into_iter()
+                .flatten()
+                .filter_map(|&child| child_bounds.get(child).ok())
+                .map(|(tf, aabb)| {
+                    tf.translation.y + (aabb.center.y + aabb.half_extents.y) * tf.scale.y
+                });
+            let top = own_top.into_iter().chain(child_top).fold(0.0, f32::max);
+            Vec2::new(0., top + HEALTH_BAR_MARGIN + bar.style.height / 2.)
+        });
+
+        let inverse_rotation = parent_transform.rotation.inverse();
+        transform.rotation = inverse_rotation;
+        transform.translation = inverse_rotation * offset.extend(HEALTH_BAR_Z);
+    }
+}
+
 pub struct HealthBarPlugin;
 
 impl Plugin for HealthBarPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             PostUpdate,
-            (add_healthbar, apply_deferred, update_healthbar).chain(),
+            (
+                add_healthbar,
+                apply_deferred,
+                update_healthbar,
+                place_healthbar,
+            )
+                .chain()
+                .before(TransformSystem::TransformPropagate),
         );
     }
 }
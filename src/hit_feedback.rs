@@ -0,0 +1,228 @@
+use bevy::{prelude::*, text::Text2dBounds, utils::HashSet};
+use rand::Rng;
+
+use crate::health::{DamageEvent, HealthSet};
+
+const DAMAGE_NUMBER_POOL_SIZE: usize = 64;
+
+const DAMAGE_NUMBER_LIFETIME: f32 = 0.6;
+
+const DAMAGE_NUMBER_SPEED: f32 = 60.0;
+
+const DAMAGE_NUMBER_Z: f32 = 50.0;
+
+const FLASH_SECS: f32 = 0.08;
+
+/// Color multiplied with textures to make them appear white.
+const FLASH_COLOR: Color = Color::rgb(10.0, 10.0, 10.0);
+
+pub struct HitFeedbackPlugin;
+
+impl Plugin for HitFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, startup)
+            .add_systems(
+                PostUpdate,
+                (show_damage_numbers, start_hit_flash)
+                    .after(HealthSet::Damage)
+                    .before(HealthSet::Despawn),
+            )
+            .add_systems(Update, (update_damage_numbers, update_hit_flash));
+    }
+}
+
+#[derive(Resource)]
+struct FlashMaterial(Handle<ColorMaterial>);
+
+/// Text entities reused for the damage numbers. The oldest one is taken when all are in use.
+#[derive(Resource)]
+struct DamageNumberPool {
+    entities: Vec<Entity>,
+    next: usize,
+}
+
+#[derive(Component, Default)]
+struct DamageNumber {
+    age: f32,
+    color: Color,
+}
+
+fn startup(mut commands: Commands, mut color_materials: ResMut<Assets<ColorMaterial>>) {
+    commands.insert_resource(FlashMaterial(
+        color_materials.add(ColorMaterial::from(Color::WHITE)),
+    ));
+
+    let entities = (0..DAMAGE_NUMBER_POOL_SIZE)
+        .map(|_| {
+            commands
+                .spawn((
+                    Name::new("DamageNumber"),
+                    DamageNumber::default(),
+                    Text2dBundle {
+                        text: Text::from_section("", TextStyle::default())
+                            .with_alignment(TextAlignment::Center),
+                        text_2d_bounds: Text2dBounds::UNBOUNDED,
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                ))
+                .id()
+        })
+        .collect();
+    commands.insert_resource(DamageNumberPool { entities, next: 0 });
+}
+
+fn show_damage_numbers(
+    mut damage_events: EventReader<DamageEvent>,
+    targets: Query<&GlobalTransform>,
+    mut pool: ResMut<DamageNumberPool>,
+    mut numbers: Query<(
+        &mut DamageNumber,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let mut rng = rand::thread_rng();
+    for ev in damage_events.read() {
+        let Ok(target) = targets.get(ev.target) else {
+            continue;
+        };
+
+        let id = pool.entities[pool.next];
+        pool.next = (pool.next + 1) % pool.entities.len();
+        let Ok((mut number, mut text, mut transform, mut vis)) = numbers.get_mut(id) else {
+            continue;
+        };
+
+        let (value, color, font_size) = if ev.critical {
+            (format!("{:.0}!", ev.amount), Color::YELLOW, 28.)
+        } else {
+            (format!("{:.0}", ev.amount), Color::WHITE, 20.)
+        };
+        let section = &mut text.sections[0];
+        section.value = value;
+        section.style.color = color;
+        section.style.font_size = font_size;
+
+        number.age = 0.;
+        number.color = color;
+        let jitter = Vec2::new(rng.gen::<f32>() - 0.5, rng.gen::<f32>() - 0.5) * 20.;
+        transform.translation = (target.translation().xy() + jitter).extend(DAMAGE_NUMBER_Z);
+        *vis = Visibility::Visible;
+    }
+}
+
+// Rises and fades out the damage numbers in use.
+fn update_damage_numbers(
+    mut numbers: Query<(
+        &mut DamageNumber,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    time: Res<Time<Virtual>>,
+) {
+    let dt = time.delta_seconds();
+    for (mut number, mut text, mut transform, mut vis) in &mut numbers {
+        if *vis == Visibility::Hidden {
+            continue;
+        }
+
+        number.age += dt;
+        if number.age >= DAMAGE_NUMBER_LIFETIME {
+            *vis = Visibility::Hidden;
+            continue;
+        }
+
+        transform.translation.y += DAMAGE_NUMBER_SPEED * dt;
+        let alpha = 1. - number.age / DAMAGE_NUMBER_LIFETIME;
+        text.sections[0].style.color = number.color.with_a(alpha);
+    }
+}
+
+enum FlashOriginal {
+    Sprite(Color),
+    Material(Handle<ColorMaterial>),
+}
+
+/// Tints the entity and its children white for a moment after taking damage.
+#[derive(Component)]
+struct HitFlash {
+    timer: Timer,
+    originals: Vec<(Entity, FlashOriginal)>,
+}
+
+fn start_hit_flash(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut flashing: Query<&mut HitFlash>,
+    children: Query<&Children>,
+    mut sprites: Query<&mut Sprite>,
+    mut materials: Query<&mut Handle<ColorMaterial>>,
+    flash_material: Res<FlashMaterial>,
+) {
+    // Several events for the same entity in a frame only start one flash.
+    let mut started = HashSet::new();
+    for ev in damage_events.read() {
+        if let Ok(mut flash) = flashing.get_mut(ev.target) {
+            flash.timer.reset();
+            continue;
+        }
+        if !started.insert(ev.target) {
+            continue;
+        }
+
+        let mut originals = Vec::new();
+        let targets = std::iter::once(ev.target)
+            .chain(children.get(ev.target).into_iter().flatten().copied())
+            .collect::<Vec<_>>();
+        for entity in targets {
+            if let Ok(mut sprite) = sprites.get_mut(entity) {
+                originals.push((entity, FlashOriginal::Sprite(sprite.color)));
+                sprite.color = FLASH_COLOR;
+            } else if let Ok(mut material) = materials.get_mut(entity) {
+                let original = std::mem::replace(&mut *material, flash_material.0.clone());
+                originals.push((entity, FlashOriginal::Material(original)));
+            }
+        }
+        if originals.is_empty() {
+            continue;
+        }
+
+        commands.entity(ev.target).insert(HitFlash {
+            timer: Timer::from_seconds(FLASH_SECS, TimerMode::Once),
+            originals,
+        });
+    }
+}
+
+fn update_hit_flash(
+    mut commands: Commands,
+    mut flashing: Query<(Entity, &mut HitFlash)>,
+    mut sprites: Query<&mut Sprite>,
+    mut materials: Query<&mut Handle<ColorMaterial>>,
+    time: Res<Time<Virtual>>,
+) {
+    for (id, mut flash) in &mut flashing {
+        if !flash.timer.tick(time.delta()).finished() {
+            continue;
+        }
+
+        for (entity, original) in flash.originals.drain(..) {
+            match original {
+                FlashOriginal::Sprite(color) => {
+                    if let Ok(mut sprite) = sprites.get_mut(entity) {
+                        sprite.color = color;
+                    }
+                }
+                FlashOriginal::Material(handle) => {
+                    if let Ok(mut material) = materials.get_mut(entity) {
+                        *material = handle;
+                    }
+                }
+            }
+        }
+        commands.entity(id).remove::<HitFlash>();
+    }
+}
@@ -0,0 +1,312 @@
+use bevy::prelude::*;
+
+use crate::{
+    boss::{Boss, BOSS_PADDING, BOSS_PHASES, BOSS_SIZE},
+    health::Health,
+    level::Level,
+    lives::Lives,
+    player::{Player, ShotType, MIN_ATTACK_RADIUS},
+    score::Score,
+    state_scoped::StateScoped,
+    GameState, MainCamera, SCREEN_HEIGHT,
+};
+
+/// Radius shown as a full radius meter.
+const METER_MAX_RADIUS: f32 = 50.0;
+
+const METER_HEIGHT: f32 = 200.0;
+
+const BAR_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Main), spawn_hud)
+            .add_systems(
+                Update,
+                (
+                    update_radius_meter,
+                    update_shot_type,
+                    update_score,
+                    update_lives,
+                    update_stage_progress,
+                    update_boss_health,
+                )
+                    .run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct RadiusFill;
+
+#[derive(Component)]
+struct RadiusText;
+
+#[derive(Component)]
+struct ShotTypeText;
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct LivesText;
+
+#[derive(Component)]
+struct StageProgressFill;
+
+#[derive(Component)]
+struct BossHealthBar;
+
+#[derive(Component)]
+struct BossHealthFill;
+
+fn spawn_hud(mut commands: Commands) {
+    let text_style = TextStyle {
+        font_size: 20.,
+        color: Color::WHITE,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Name::new("HUD"),
+            StateScoped(GameState::Main),
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.)),
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            // Boss health bar, shown while the boss is alive
+            c.spawn((
+                BossHealthBar,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Px(12.),
+                        display: Display::None,
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND.into(),
+                    ..default()
+                },
+            ))
+            .with_children(|c| {
+                c.spawn((
+                    BossHealthFill,
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        background_color: Color::BLUE.with_a(0.8).into(),
+                        ..default()
+                    },
+                ));
+                for phase in BOSS_PHASES {
+                    c.spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Percent(phase * 100.),
+                            width: Val::Px(2.),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    });
+                }
+            });
+
+            // Stage progress, lives and score
+            c.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|c| {
+                c.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.),
+                        height: Val::Px(6.),
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|c| {
+                    c.spawn((
+                        StageProgressFill,
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.),
+                                height: Val::Percent(100.),
+                                ..default()
+                            },
+                            background_color: Color::CYAN.into(),
+                            ..default()
+                        },
+                    ));
+                });
+                c.spawn((LivesText, TextBundle::from_section("", text_style.clone())));
+                c.spawn((ScoreText, TextBundle::from_section("", text_style.clone())));
+            });
+
+            // Radius meter
+            c.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.),
+                    margin: UiRect::top(Val::Auto),
+                    width: Val::Px(72.),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|c| {
+                c.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(12.),
+                        height: Val::Px(METER_HEIGHT),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|c| {
+                    c.spawn((
+                        RadiusFill,
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.),
+                                height: Val::Percent(100.),
+                                ..default()
+                            },
+                            background_color: Color::CYAN.into(),
+                            ..default()
+                        },
+                    ));
+                    // Minimum radius to fire
+                    c.spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            bottom: Val::Percent(MIN_ATTACK_RADIUS / METER_MAX_RADIUS * 100.),
+                            left: Val::Px(-4.),
+                            width: Val::Px(20.),
+                            height: Val::Px(2.),
+                            ..default()
+                        },
+                        background_color: Color::RED.into(),
+                        ..default()
+                    });
+                });
+                c.spawn((RadiusText, TextBundle::from_section("", text_style.clone())));
+                c.spawn((ShotTypeText, TextBundle::from_section("", text_style)));
+            });
+        });
+}
+
+fn update_radius_meter(
+    player: Query<&Player>,
+    mut fill: Query<(&mut Style, &mut BackgroundColor), With<RadiusFill>>,
+    mut text: Query<&mut Text, With<RadiusText>>,
+) {
+    let radius = player.get_single().map_or(0., |player| player.radius);
+
+    for (mut style, mut color) in &mut fill {
+        style.height = Val::Percent((radius / METER_MAX_RADIUS).min(1.) * 100.);
+        *color = if radius < MIN_ATTACK_RADIUS {
+            Color::RED.into()
+        } else {
+            Color::CYAN.into()
+        };
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!("{radius:.1}");
+    }
+}
+
+fn update_shot_type(
+    player: Query<&ShotType, Changed<ShotType>>,
+    mut text: Query<&mut Text, With<ShotTypeText>>,
+) {
+    let Ok(shot_type) = player.get_single() else {
+        return;
+    };
+    for mut text in &mut text {
+        text.sections[0].value = shot_type.name().to_string();
+    }
+}
+
+fn update_score(score: Res<Score>, mut text: Query<&mut Text, With<ScoreText>>) {
+    for mut text in &mut text {
+        text.sections[0].value = format!("Score: {}", score.0);
+    }
+}
+
+fn update_lives(lives: Res<Lives>, mut text: Query<&mut Text, With<LivesText>>) {
+    for mut text in &mut text {
+        text.sections[0].value = format!("Lives: {}", lives.remaining);
+    }
+}
+
+fn update_stage_progress(
+    level: Option<Res<Level>>,
+    camera: Query<&Transform, With<MainCamera>>,
+    mut fill: Query<&mut Style, With<StageProgressFill>>,
+    mut scroll_end: Local<f32>,
+) {
+    // Camera y where the scrolling stops at the boss
+    if let Some(boss_pos) = level.and_then(|level| level.boss_pos) {
+        *scroll_end = boss_pos.y + SCREEN_HEIGHT / 2. - BOSS_SIZE / 2. - BOSS_PADDING;
+    }
+    if *scroll_end >= 0. {
+        return;
+    }
+
+    let progress = (camera.single().translation.y / *scroll_end).clamp(0., 1.);
+    for mut style in &mut fill {
+        style.width = Val::Percent(progress * 100.);
+    }
+}
+
+fn update_boss_health(
+    boss: Query<&Health, With<Boss>>,
+    mut bar: Query<&mut Style, (With<BossHealthBar>, Without<BossHealthFill>)>,
+    mut fill: Query<&mut Style, (With<BossHealthFill>, Without<BossHealthBar>)>,
+) {
+    let health = boss.get_single().ok();
+
+    for mut style in &mut bar {
+        let display = if health.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+
+    if let Some(health) = health {
+        for mut style in &mut fill {
+            style.width = Val::Percent(health.percent());
+        }
+    }
+}
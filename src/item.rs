@@ -1,7 +1,14 @@
 use bevy::{prelude::*, sprite::Mesh2dHandle};
 use bevy_xpbd_2d::prelude::*;
+use rand::Rng;
 
-use crate::MyLayer;
+use crate::{
+    culling::Cull,
+    difficulty::Difficulty,
+    health::{Died, HealthSet},
+    state_scoped::StateScoped,
+    GameState, MyLayer,
+};
 
 #[derive(Resource)]
 struct ItemResource {
@@ -17,6 +24,45 @@ fn startup(mut commands: Commands, mut color_materials: ResMut<Assets<ColorMater
 #[derive(Component)]
 pub struct Item;
 
+/// Sent when the player picks up an item.
+#[derive(Event)]
+pub struct ItemCollectedEvent;
+
+/// Drops items around the entity when it dies.
+#[derive(Component)]
+pub struct DropItems {
+    /// Number of items on normal difficulty.
+    pub count: u32,
+    /// Half size of the square the items are scattered in.
+    pub spread: f32,
+}
+
+fn drop_items_on_death(
+    mut commands: Commands,
+    mut died_events: EventReader<Died>,
+    q: Query<(&DropItems, &GlobalTransform)>,
+    difficulty: Res<Difficulty>,
+) {
+    let mut rng = rand::thread_rng();
+    for ev in died_events.read() {
+        let Ok((drop, transform)) = q.get(ev.entity) else {
+            continue;
+        };
+
+        for _ in 0..difficulty.item_count(drop.count) {
+            let dev = Vec2::new(rng.gen::<f32>() - 0.5, rng.gen::<f32>() - 0.5) * drop.spread * 2.;
+
+            commands.spawn((
+                Item,
+                SpatialBundle::from_transform(Transform::from_translation(
+                    transform.translation() + dev.extend(0.0),
+                )),
+                StateScoped(GameState::Main),
+            ));
+        }
+    }
+}
+
 fn spawn_item(
     mut commands: Commands,
     q: Query<Entity, Added<Item>>,
@@ -30,6 +76,7 @@ fn spawn_item(
                 Collider::ball(8.),
                 RigidBody::Kinematic,
                 CollisionLayers::new([MyLayer::Item], [MyLayer::Player]),
+                Cull { margin: 8. },
             ))
             .with_children(|c| {
                 c.spawn(ColorMesh2dBundle {
@@ -53,7 +100,9 @@ pub struct ItemPlugin;
 
 impl Plugin for ItemPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, startup)
-            .add_systems(PostUpdate, spawn_item);
+        app.add_event::<ItemCollectedEvent>()
+            .add_systems(Startup, startup)
+            .add_systems(PostUpdate, spawn_item)
+            .add_systems(PostUpdate, drop_items_on_death.in_set(HealthSet::Death));
     }
 }
@@ -1,22 +1,40 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    audio::MusicEvent,
     boss::{Boss, BOSS_PADDING, BOSS_SIZE},
-    enemy::{spawn_enemy, EnemyController, ENEMY_SIZE},
-    MainCamera, ScrollSpeed, SCREEN_HEIGHT,
+    enemy::{spawn_enemy, EnemyController, EnemyKind, ENEMY_SIZE},
+    state_scoped::StateScoped,
+    GameState, MainCamera, ScrollSpeed, SCREEN_HEIGHT, SCREEN_WIDTH,
 };
 
+const LEVEL_PATH: &str = "level.ron";
+
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ScrollDoneEvent>()
             // .add_systems(Startup, setup)
+            .insert_resource(LevelData::load())
+            .add_systems(OnEnter(GameState::Main), setup_level)
+            .add_systems(OnEnter(GameState::Title), |mut commands: Commands| {
+                commands.remove_resource::<StageStart>();
+            })
             .add_systems(
                 Update,
                 (scroll_system, spawn_enemies)
                     .chain()
                     .run_if(resource_exists::<Level>()),
+            )
+            .add_systems(
+                Update,
+                (
+                    play_stage_music.run_if(resource_added::<Level>()),
+                    play_boss_music.run_if(on_event::<ScrollDoneEvent>()),
+                )
+                    .run_if(resource_exists::<Level>()),
             );
     }
 }
@@ -25,11 +43,186 @@ impl Plugin for LevelPlugin {
 pub struct Level {
     pub enemies: Vec<(Vec2, EnemyController)>,
     pub boss_pos: Option<Vec2>,
+    /// Music played while scrolling through the stage.
+    pub music: Option<String>,
+    /// Music crossfaded to when the boss fight starts.
+    pub boss_music: Option<String>,
 }
 
 #[derive(Event)]
 pub struct ScrollDoneEvent;
 
+/// The stage as edited in the editor, persisted to [`LEVEL_PATH`].
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct LevelData {
+    pub enemies: Vec<EnemyPlacement>,
+    pub boss_pos: Option<Vec2>,
+    pub music: Option<String>,
+    pub boss_music: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EnemyPlacement {
+    pub pos: Vec2,
+    /// Positions in the order they are visited. The enemy spawns when the camera reaches the
+    /// first one and leaves through the last one.
+    pub waypoints: Vec<Vec2>,
+    #[serde(default)]
+    pub kind: EnemyKind,
+}
+
+impl LevelData {
+    /// The built-in stage, used when there is no level file.
+    pub fn stage1() -> Self {
+        let mut enemies = Vec::new();
+        for i in 0..3 {
+            let y = -i as f32 * 100.;
+            enemies.push(EnemyPlacement {
+                pos: Vec2::new(-SCREEN_WIDTH / 2. - ENEMY_SIZE / 2., y),
+                waypoints: vec![
+                    Vec2::new(SCREEN_WIDTH / 3. - SCREEN_WIDTH / 2., y),
+                    Vec2::new(2. * SCREEN_WIDTH / 3. - SCREEN_WIDTH / 2., y - 100.),
+                    Vec2::new(SCREEN_WIDTH / 2. + ENEMY_SIZE / 2., y),
+                ],
+                kind: EnemyKind::Normal,
+            });
+        }
+
+        for i in 0..3 {
+            let y = -i as f32 * 100. - 400.;
+            enemies.push(EnemyPlacement {
+                pos: Vec2::new(SCREEN_WIDTH / 2. + ENEMY_SIZE / 2., y),
+                waypoints: vec![
+                    Vec2::new(SCREEN_WIDTH / 3. + SCREEN_WIDTH / 2., y),
+                    Vec2::new(-SCREEN_WIDTH / 3. + SCREEN_WIDTH / 2., y),
+                    Vec2::new(-SCREEN_WIDTH / 2. - ENEMY_SIZE / 2., y),
+                ],
+                kind: EnemyKind::Normal,
+            });
+        }
+
+        enemies.push(EnemyPlacement {
+            pos: Vec2::new(0., -900.),
+            waypoints: vec![
+                Vec2::new(0., -800.),
+                Vec2::new(0., -750.),
+                Vec2::new(SCREEN_WIDTH / 3., -800.),
+                Vec2::new(SCREEN_WIDTH / 2. + ENEMY_SIZE / 2., -800.),
+            ],
+            kind: EnemyKind::Normal,
+        });
+        // Ice enemies guarding the boss
+        for x in [-SCREEN_WIDTH / 4., SCREEN_WIDTH / 4.] {
+            let start = Vec2::new(x, -SCREEN_HEIGHT / 2. - 900.);
+            enemies.push(EnemyPlacement {
+                pos: start,
+                waypoints: vec![Vec2::new(x, -850.), start],
+                kind: EnemyKind::Ice,
+            });
+        }
+
+        Self {
+            enemies,
+            boss_pos: Vec2::new(0., -1000.).into(),
+            // No tracks yet, levels can name files under `assets/` once they exist.
+            music: None,
+            boss_music: None,
+        }
+    }
+
+    fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(LEVEL_PATH) else {
+            return Self::stage1();
+        };
+        ron::from_str(&text).unwrap_or_else(|e| {
+            warn!("Failed to parse {LEVEL_PATH}: {e}");
+            Self::stage1()
+        })
+    }
+
+    /// Writes the level to [`LEVEL_PATH`], returning whether it succeeded.
+    pub fn save(&self) -> bool {
+        let text = match ron::ser::to_string_pretty(self, default()) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Failed to serialize level: {e}");
+                return false;
+            }
+        };
+        if let Err(e) = std::fs::write(LEVEL_PATH, text) {
+            warn!("Failed to write {LEVEL_PATH}: {e}");
+            return false;
+        }
+        true
+    }
+
+    pub fn boss_camera_y(&self) -> Option<f32> {
+        self.boss_pos.map(boss_camera_y)
+    }
+
+    fn to_level(&self) -> Level {
+        Level {
+            enemies: self
+                .enemies
+                .iter()
+                .filter(|enemy| !enemy.waypoints.is_empty())
+                .map(|enemy| {
+                    // The controller pops its waypoints from the end.
+                    let waypoints = enemy.waypoints.iter().rev().copied().collect::<Vec<_>>();
+                    (
+                        enemy.pos,
+                        EnemyController::from(waypoints).with_kind(enemy.kind),
+                    )
+                })
+                .collect(),
+            boss_pos: self.boss_pos,
+            music: self.music.clone(),
+            boss_music: self.boss_music.clone(),
+        }
+    }
+}
+
+/// Camera y at which the scrolling stops with the boss at the bottom of the screen.
+pub fn boss_camera_y(boss_pos: Vec2) -> f32 {
+    boss_pos.y + SCREEN_HEIGHT / 2. - BOSS_SIZE / 2. - BOSS_PADDING
+}
+
+/// Where the stage starts instead of the top, for practice and testing.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct StageStart {
+    pub position: StartPosition,
+    /// Player radius instead of the default one.
+    pub player_radius: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartPosition {
+    CameraY(f32),
+    /// Where the scrolling stops for the boss fight.
+    Boss,
+}
+
+pub fn setup_level(
+    mut commands: Commands,
+    data: Res<LevelData>,
+    start: Option<Res<StageStart>>,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    let mut level = data.to_level();
+    if let Some(start) = start {
+        let camera_y = match start.position {
+            StartPosition::CameraY(y) => y,
+            StartPosition::Boss => data.boss_camera_y().unwrap_or(0.),
+        };
+        camera.single_mut().translation.y = camera_y;
+        // Skip the enemies whose trigger point was passed already.
+        level
+            .enemies
+            .retain(|(_, enemy)| enemy.attack_pos.last().unwrap().y <= camera_y);
+    }
+    commands.insert_resource(level);
+}
+
 fn scroll_system(
     mut camera: Query<&mut Transform, With<MainCamera>>,
     boss: Query<&Transform, (With<Boss>, Without<MainCamera>)>,
@@ -61,6 +254,18 @@ fn scroll_system(
     camera.translation.y -= 60. * time.delta_seconds() * scroll_speed.0;
 }
 
+fn play_stage_music(level: Res<Level>, mut music: EventWriter<MusicEvent>) {
+    if let Some(path) = &level.music {
+        music.send(MusicEvent::Play(path.clone()));
+    }
+}
+
+fn play_boss_music(level: Res<Level>, mut music: EventWriter<MusicEvent>) {
+    if let Some(path) = &level.boss_music {
+        music.send(MusicEvent::Play(path.clone()));
+    }
+}
+
 fn spawn_enemies(
     mut commands: Commands,
     camera: Query<&Transform, With<MainCamera>>,
@@ -86,6 +291,7 @@ fn spawn_enemies(
                 Name::new("Boss"),
                 Boss,
                 SpatialBundle::from_transform(Transform::from_translation(boss_pos.extend(0.))),
+                StateScoped(GameState::Main),
             ));
         }
     }
@@ -0,0 +1,398 @@
+use bevy::prelude::*;
+
+use crate::{
+    bullet_pool::release_bullet,
+    difficulty::Difficulty,
+    enemy::EnemyBullet,
+    item::ItemCollectedEvent,
+    pause::PauseState,
+    player::{Player, PlayerDiedEvent},
+    score::{HighScores, Score},
+    settings::Settings,
+    state_scoped::StateScoped,
+    title::GameMode,
+    GameState, MainCamera, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+const BUTTON_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const BUTTON_HOVERED_COLOR: Color = Color::rgba(0.0, 0.5, 0.5, 0.6);
+
+const MAX_LIVES: u32 = 9;
+
+/// Scores giving an extra life.
+const EXTRA_LIFE_SCORES: [u32; 3] = [2_000, 5_000, 10_000];
+
+/// Number of items to collect for an extra life.
+const EXTRA_LIFE_ITEMS: u32 = 300;
+
+const RESPAWN_SECS: f32 = 1.0;
+
+const INVULNERABLE_SECS: f32 = 2.0;
+
+const BLINK_SECS: f32 = 0.1;
+
+const CONTINUE_SECS: f32 = 10.0;
+
+pub struct LivesPlugin;
+
+impl Plugin for LivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameOverEvent>()
+            .init_resource::<Lives>()
+            .add_systems(OnEnter(GameState::Main), reset_lives)
+            .add_systems(
+                Update,
+                (
+                    player_died.run_if(on_event::<PlayerDiedEvent>()),
+                    extra_lives,
+                    respawn.run_if(resource_exists::<Respawn>()),
+                    invulnerability,
+                )
+                    .run_if(in_state(GameState::Main).and_then(in_state(PauseState::Running))),
+            )
+            .add_systems(OnEnter(PauseState::Continue), spawn_continue_prompt)
+            .add_systems(OnExit(PauseState::Continue), despawn_continue_prompt)
+            .add_systems(
+                Update,
+                continue_prompt.run_if(in_state(PauseState::Continue)),
+            )
+            .add_systems(
+                OnExit(GameState::Main),
+                |mut commands: Commands,
+                 mut invulnerable: Query<(Entity, &mut Visibility), With<Invulnerable>>| {
+                    commands.remove_resource::<Respawn>();
+                    commands.remove_resource::<ContinuePrompt>();
+                    for (id, mut vis) in &mut invulnerable {
+                        *vis = Visibility::Inherited;
+                        commands.entity(id).remove::<Invulnerable>();
+                    }
+                },
+            );
+    }
+}
+
+/// Sent when the run ends after running out of lives without continuing.
+#[derive(Event)]
+pub struct GameOverEvent;
+
+#[derive(Resource, Default, Debug)]
+pub struct Lives {
+    /// Extra lives left.
+    pub remaining: u32,
+    /// Index into [`EXTRA_LIFE_SCORES`] of the next score giving a life.
+    next_score: usize,
+    items: u32,
+}
+
+/// Player waiting to respawn at `offset` from the camera.
+#[derive(Resource)]
+struct Respawn {
+    timer: Timer,
+    offset: Vec2,
+}
+
+/// Shown after losing the last life.
+#[derive(Resource)]
+struct ContinuePrompt {
+    timer: Timer,
+    offset: Vec2,
+}
+
+/// Makes the player ignore enemy bullets.
+#[derive(Component)]
+pub struct Invulnerable {
+    /// Time left blinking, or `None` to stay invulnerable until removed.
+    timer: Option<Timer>,
+    blink: Timer,
+}
+
+impl Invulnerable {
+    fn new() -> Self {
+        Self {
+            timer: Some(Timer::from_seconds(INVULNERABLE_SECS, TimerMode::Once)),
+            blink: Timer::from_seconds(BLINK_SECS, TimerMode::Repeating),
+        }
+    }
+
+    pub fn forever() -> Self {
+        Self {
+            timer: None,
+            blink: Timer::from_seconds(BLINK_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+fn reset_lives(mut lives: ResMut<Lives>, settings: Res<Settings>) {
+    *lives = Lives {
+        remaining: settings.lives,
+        ..default()
+    };
+}
+
+fn player_died(
+    mut commands: Commands,
+    mut events: EventReader<PlayerDiedEvent>,
+    mut lives: ResMut<Lives>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    camera: Query<&Transform, With<MainCamera>>,
+) {
+    let camera = camera.single().translation.xy();
+    for ev in events.read() {
+        let offset = ev.position - camera;
+        if lives.remaining > 0 {
+            lives.remaining -= 1;
+            commands.insert_resource(Respawn {
+                timer: Timer::from_seconds(RESPAWN_SECS, TimerMode::Once),
+                offset,
+            });
+        } else {
+            commands.insert_resource(ContinuePrompt {
+                timer: Timer::from_seconds(CONTINUE_SECS, TimerMode::Once),
+                offset,
+            });
+            pause_state.set(PauseState::Continue);
+        }
+    }
+}
+
+fn extra_lives(
+    mut lives: ResMut<Lives>,
+    score: Res<Score>,
+    mut collected: EventReader<ItemCollectedEvent>,
+) {
+    let mut gained = 0;
+    while EXTRA_LIFE_SCORES
+        .get(lives.next_score)
+        .is_some_and(|&threshold| score.0 >= threshold)
+    {
+        lives.next_score += 1;
+        gained += 1;
+    }
+
+    for _ in collected.read() {
+        lives.items += 1;
+        if lives.items >= EXTRA_LIFE_ITEMS {
+            lives.items = 0;
+            gained += 1;
+        }
+    }
+
+    if gained > 0 {
+        lives.remaining = (lives.remaining + gained).min(MAX_LIVES);
+    }
+}
+
+fn respawn(
+    mut commands: Commands,
+    mut respawn: ResMut<Respawn>,
+    camera: Query<&Transform, With<MainCamera>>,
+    bullets: Query<Entity, With<EnemyBullet>>,
+    time: Res<Time<Virtual>>,
+) {
+    if !respawn.timer.tick(time.delta()).finished() {
+        return;
+    }
+    commands.remove_resource::<Respawn>();
+
+    for bullet in &bullets {
+        release_bullet(&mut commands, bullet);
+    }
+
+    let half_screen = Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.;
+    let offset = respawn.offset.clamp(-half_screen, half_screen);
+    let pos = camera.single().translation.xy() + offset;
+    commands.spawn((
+        Player::default(),
+        Transform::from_translation(pos.extend(2.)),
+        Invulnerable::new(),
+        StateScoped(GameState::Main),
+    ));
+}
+
+fn invulnerability(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+    time: Res<Time<Virtual>>,
+) {
+    for (id, mut invulnerable, mut vis) in &mut q {
+        let Some(timer) = invulnerable.timer.as_mut() else {
+            continue;
+        };
+        if timer.tick(time.delta()).finished() {
+            *vis = Visibility::Inherited;
+            commands.entity(id).remove::<Invulnerable>();
+            continue;
+        }
+        if invulnerable.blink.tick(time.delta()).just_finished() {
+            *vis = match *vis {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+#[derive(Component)]
+struct ContinueMenu;
+
+#[derive(Component)]
+struct ContinueCountdown;
+
+#[derive(Component, Clone, Copy)]
+enum ContinueButton {
+    Yes,
+    No,
+}
+
+fn spawn_continue_prompt(mut commands: Commands) {
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::WHITE,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Name::new("Continue menu"),
+            ContinueMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.),
+                    ..default()
+                },
+                background_color: Color::BLACK.with_a(0.4).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn(TextBundle::from_section(
+                "CONTINUE?",
+                TextStyle {
+                    font_size: 48.,
+                    color: Color::CYAN,
+                    ..default()
+                },
+            ));
+            c.spawn((
+                ContinueCountdown,
+                TextBundle::from_section("", text_style(32.)),
+            ));
+            c.spawn(TextBundle::from_section(
+                "Continuing resets your score",
+                text_style(16.),
+            ));
+
+            c.spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(12.),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|c| {
+                for (button, label) in [(ContinueButton::Yes, "Yes"), (ContinueButton::No, "No")] {
+                    c.spawn((
+                        button,
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(120.),
+                                height: Val::Px(40.),
+                                align_items: AlignItems::Center,
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|c| {
+                        c.spawn(TextBundle::from_section(label, text_style(24.)));
+                    });
+                }
+            });
+        });
+}
+
+fn despawn_continue_prompt(mut commands: Commands, menu: Query<Entity, With<ContinueMenu>>) {
+    for e in &menu {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+// Yes (Enter, Y) continues with the score reset, No (N) or the countdown ends the run.
+#[allow(clippy::too_many_arguments)]
+fn continue_prompt(
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &ContinueButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut countdown: Query<&mut Text, With<ContinueCountdown>>,
+    prompt: Option<ResMut<ContinuePrompt>>,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time<Real>>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    mut game_over: EventWriter<GameOverEvent>,
+    mut lives: ResMut<Lives>,
+    mut score: ResMut<Score>,
+    mut high_scores: ResMut<HighScores>,
+    difficulty: Res<Difficulty>,
+    settings: Res<Settings>,
+    mode: Res<GameMode>,
+) {
+    let Some(mut prompt) = prompt else {
+        return;
+    };
+
+    let mut choice = None;
+    for (interaction, button, mut color) in &mut buttons {
+        match interaction {
+            Interaction::Pressed => choice = Some(*button),
+            Interaction::Hovered => *color = BUTTON_HOVERED_COLOR.into(),
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+    if keys.any_just_pressed([KeyCode::Return, KeyCode::Y]) {
+        choice = Some(ContinueButton::Yes);
+    } else if keys.just_pressed(KeyCode::N) {
+        choice = Some(ContinueButton::No);
+    }
+
+    prompt.timer.tick(time.delta());
+    if prompt.timer.finished() {
+        choice = Some(ContinueButton::No);
+    }
+    for mut text in &mut countdown {
+        text.sections[0].value = format!("{}", prompt.timer.remaining_secs().ceil());
+    }
+
+    let Some(choice) = choice else {
+        return;
+    };
+    commands.remove_resource::<ContinuePrompt>();
+    pause_state.set(PauseState::Running);
+
+    match choice {
+        ContinueButton::Yes => {
+            // The score so far still counts for the high scores.
+            if *mode != GameMode::Practice {
+                high_scores.submit(*difficulty, score.0);
+            }
+            score.0 = 0;
+            *lives = Lives {
+                remaining: settings.lives,
+                ..default()
+            };
+            commands.insert_resource(Respawn {
+                timer: Timer::from_seconds(0., TimerMode::Once),
+                offset: prompt.offset,
+            });
+        }
+        ContinueButton::No => {
+            game_over.send(GameOverEvent);
+        }
+    }
+}
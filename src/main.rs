@@ -1,33 +1,70 @@
+mod audio;
 mod background;
+mod bench;
 mod boss;
+mod bullet_collision;
+mod bullet_pool;
+mod camera;
+mod culling;
 mod damage;
+#[cfg(feature = "dev")]
+mod debug;
+mod difficulty;
+mod drop_text;
+mod editor;
 mod enemy;
 mod health;
+mod hit_feedback;
+mod hud;
 mod item;
 mod level;
+mod lives;
+mod particle;
+mod pause;
 mod player;
+mod score;
+mod settings;
+mod state_scoped;
 mod title;
 
+use audio::SoundPlugin;
 use background::{Background2dBundle, BackgroundMaterial, BackgroundPlugin};
+use bench::{BulletBenchPlugin, BENCH_FLAG};
+#[cfg(feature = "web")]
+use bevy::asset::AssetMetaCheck;
 use bevy::{
-    asset::AssetMetaCheck,
-    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    diagnostic::FrameTimeDiagnosticsPlugin,
     prelude::*,
     sprite::Mesh2dHandle,
-    window::{PrimaryWindow, WindowResolution},
+    window::{PresentMode, PrimaryWindow, WindowResolution},
 };
-use bevy_debug_text_overlay::{screen_print, OverlayPlugin};
+#[cfg(feature = "dev")]
+use bevy_debug_text_overlay::screen_print;
 use bevy_framepace::FramepacePlugin;
-// use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_tweening::Animator;
 use bevy_xpbd_2d::prelude::*;
-use boss::{Boss, BossPlugin};
+use boss::BossPlugin;
+use bullet_collision::BulletCollisionPlugin;
+use bullet_pool::{release_bullet, BulletPoolPlugin};
+use camera::{remove_camera_effects, CameraEffectsPlugin};
+use culling::CullingPlugin;
 use damage::{BossDiedEvent, DamagePlugin};
-use enemy::{Enemy, EnemyBullet, EnemyPlugin, ENEMY_SIZE};
-use health::HealthBarPlugin;
-use item::{Item, ItemPlugin};
-use level::{Level, LevelPlugin};
-use player::{Player, PlayerBullet, PlayerDiedEvent, PlayerPlugin};
+use difficulty::DifficultyPlugin;
+use drop_text::DropTextPlugin;
+use editor::{EditorPlugin, Playtest};
+use enemy::{EnemyBullet, EnemyPlugin};
+use health::{HealthBarPlugin, HealthPlugin};
+use hit_feedback::HitFeedbackPlugin;
+use hud::HudPlugin;
+use item::ItemPlugin;
+use level::{setup_level, Level, LevelPlugin, StageStart};
+use lives::{GameOverEvent, LivesPlugin};
+use particle::ParticlePlugin;
+use pause::{PausePlugin, PauseState};
+use player::{Player, PlayerPlugin};
+use score::ScorePlugin;
+use settings::{ControlScheme, FrameLimit, Settings, SettingsPlugin};
+use state_scoped::{StateScoped, StateScopedPlugin};
 use title::{TitlePlugin, TitleRoot};
 
 pub const SCREEN_WIDTH: f32 = 800.0;
@@ -36,9 +73,7 @@ pub const SCREEN_HEIGHT: f32 = 600.0;
 #[derive(PhysicsLayer)]
 pub enum MyLayer {
     Player,
-    PlayerBullet,
     Enemy,
-    EnemyBullet,
     Item,
 }
 
@@ -48,16 +83,34 @@ enum GameState {
     Title,
     Main,
     GameOver,
+    Editor,
 }
 
 fn main() {
     let mut app = App::new();
 
-    app.insert_resource(AssetMetaCheck::Never).add_plugins(
+    let mut settings = Settings::load();
+    // Measure the actual frame time instead of the frame limit.
+    let bench = std::env::args().any(|arg| arg == BENCH_FLAG);
+    if bench {
+        settings.frame_limit = FrameLimit::Off;
+    }
+
+    // Web hosts answer requests for missing `.meta` files with errors or HTML pages.
+    #[cfg(feature = "web")]
+    app.insert_resource(AssetMetaCheck::Never);
+
+    app.add_plugins(
         DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                resolution: WindowResolution::new(SCREEN_WIDTH, SCREEN_HEIGHT),
+                resolution: WindowResolution::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+                    .with_scale_factor_override(settings.window_scale as f64),
                 resizable: false,
+                present_mode: if bench {
+                    PresentMode::AutoNoVsync
+                } else {
+                    PresentMode::AutoVsync
+                },
                 ..default()
             }),
             ..default()
@@ -72,38 +125,60 @@ fn main() {
     //     218. / 255. / 2.,
     // )));
 
-    app.add_plugins(FrameTimeDiagnosticsPlugin::default())
-        .add_systems(Update, |diagnostics: Res<DiagnosticsStore>| {
-            if let Some(fps) = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS) {
-                // screen_print!("FPS: {}", fps.average().unwrap_or_default());
-            }
-        });
+    app.add_plugins(FrameTimeDiagnosticsPlugin);
 
     app.add_plugins(PhysicsPlugins::default())
-        // .add_plugins(PhysicsDebugPlugin::default())
-        // .add_plugins(OverlayPlugin::default())
-        // .add_plugins(WorldInspectorPlugin::new())
         .add_plugins(FramepacePlugin);
 
+    #[cfg(feature = "dev")]
+    app.add_plugins(debug::DebugPlugin);
+
     app.add_state::<GameState>();
 
+    if bench {
+        app.add_plugins(BulletBenchPlugin);
+    }
+
+    app.insert_resource(settings)
+        .add_plugins(SettingsPlugin)
+        .add_plugins(SoundPlugin);
+
     app.add_plugins(PlayerPlugin)
         .add_plugins(EnemyPlugin)
+        .add_plugins(HealthPlugin)
         .add_plugins(HealthBarPlugin)
         .add_plugins(DamagePlugin)
+        .add_plugins(HitFeedbackPlugin)
+        .add_plugins(ParticlePlugin)
+        .add_plugins(CameraEffectsPlugin)
         .add_plugins(ItemPlugin)
         .add_plugins(LevelPlugin)
         .add_plugins(BossPlugin)
         .add_plugins(TitlePlugin)
+        .add_plugins(DropTextPlugin)
+        .add_plugins(EditorPlugin)
         .add_plugins(BackgroundPlugin)
+        .add_plugins(PausePlugin)
+        .add_plugins(DifficultyPlugin)
+        .add_plugins(ScorePlugin)
+        .add_plugins(HudPlugin)
+        .add_plugins(LivesPlugin)
+        .add_plugins(StateScopedPlugin)
+        .add_plugins(BulletPoolPlugin)
+        .add_plugins(BulletCollisionPlugin)
+        .add_plugins(CullingPlugin)
         .insert_resource(ScrollSpeed(1.))
         .add_systems(Startup, setup)
         .add_systems(PostUpdate, scroll_background)
-        .add_systems(PreUpdate, update_mouse_pos)
+        .add_systems(PreUpdate, update_mouse_pos.after(remove_camera_effects))
         .add_systems(
             Update,
             (|mouse_pos: Option<Res<MouseWorldPos>>,
+              settings: Res<Settings>,
               mut q: Query<&mut Transform, With<Player>>| {
+                if settings.controls.scheme != ControlScheme::Mouse {
+                    return;
+                }
                 let Some(mouse_pos) = mouse_pos else { return };
                 if !mouse_pos.is_changed() {
                     return;
@@ -115,7 +190,7 @@ fn main() {
                     transform.translation = mouse_pos.0.extend(2.0);
                 }
             })
-            .run_if(in_state(GameState::Main)),
+            .run_if(in_state(GameState::Main).and_then(in_state(PauseState::Running))),
         )
         .add_systems(
             Update,
@@ -127,12 +202,26 @@ fn main() {
             })
             .run_if(in_state(GameState::Title)),
         )
-        .add_systems(OnEnter(GameState::Main), |mut commands: Commands| {
-            // screen_print!("OnEnter(GameState::Main)");
-            commands.insert_resource(ScrollSpeed(1.));
-            commands.spawn((Player::default(), Transform::from_translation(2. * Vec3::Z)));
-        })
-        .add_systems(OnEnter(GameState::Main), setup_level)
+        .add_systems(
+            OnEnter(GameState::Main),
+            (|mut commands: Commands,
+              camera: Query<&Transform, With<MainCamera>>,
+              start: Option<Res<StageStart>>| {
+                // screen_print!("OnEnter(GameState::Main)");
+                commands.insert_resource(ScrollSpeed(1.));
+                let pos = camera.single().translation.xy();
+                let mut player = Player::default();
+                if let Some(radius) = start.and_then(|start| start.player_radius) {
+                    player.radius = radius;
+                }
+                commands.spawn((
+                    player,
+                    Transform::from_translation(pos.extend(2.)),
+                    StateScoped(GameState::Main),
+                ));
+            })
+            .after(setup_level),
+        )
         .add_systems(OnEnter(GameState::Title), |mut commands: Commands| {
             // screen_print!("OnEnter(GameState::Title)");
             commands.remove_resource::<Level>();
@@ -153,42 +242,36 @@ fn main() {
                 commands.remove_resource::<PlayerDisposition>();
                 state.set(GameState::GameOver);
             })
-            .run_if(on_event::<PlayerDiedEvent>()),
+            .run_if(on_event::<GameOverEvent>()),
         )
         .add_systems(
             Update,
             (|mut timer: ResMut<GameOverTimer>,
               time: Res<Time>,
-              mut state: ResMut<NextState<GameState>>| {
+              mut state: ResMut<NextState<GameState>>,
+              restart: Option<Res<RestartRequested>>,
+              playtest: Option<Res<Playtest>>| {
                 if timer.0.tick(time.delta()).just_finished() {
-                    // screen_print!("Go back to title");
-                    state.set(GameState::Title);
+                    if restart.is_some() {
+                        state.set(GameState::Main);
+                    } else if playtest.is_some() {
+                        state.set(GameState::Editor);
+                    } else {
+                        // screen_print!("Go back to title");
+                        state.set(GameState::Title);
+                    }
                 }
             })
             .run_if(in_state(GameState::GameOver)),
         )
         .add_systems(
             OnExit(GameState::GameOver),
-            |mut commands: Commands,
-             mut camera: Query<&mut Transform, With<MainCamera>>,
-             main_entities: Query<
-                Entity,
-                Or<(
-                    With<Enemy>,
-                    With<Boss>,
-                    With<PlayerBullet>,
-                    With<EnemyBullet>,
-                    With<Item>,
-                )>,
-            >| {
+            |mut commands: Commands, mut camera: Query<&mut Transform, With<MainCamera>>| {
                 commands.remove_resource::<GameOverTimer>();
+                commands.remove_resource::<RestartRequested>();
                 commands.remove_resource::<Level>();
 
                 camera.single_mut().translation.y = 0.;
-
-                for e in &main_entities {
-                    commands.entity(e).despawn_recursive();
-                }
             },
         )
         .add_systems(
@@ -197,7 +280,7 @@ fn main() {
               mut state: ResMut<NextState<GameState>>,
               bullets: Query<Entity, With<EnemyBullet>>| {
                 for bullet in &bullets {
-                    commands.entity(bullet).despawn_recursive();
+                    release_bullet(&mut commands, bullet);
                 }
 
                 commands.insert_resource(GameOverTimer(Timer::from_seconds(4., TimerMode::Once)));
@@ -253,65 +336,6 @@ struct ScrollSpeed(f32);
 #[derive(Resource)]
 struct PlayerDisposition(f32);
 
-fn setup_level(mut commands: Commands) {
-    let mut enemies = Vec::new();
-    for i in 0..3 {
-        let enemy_positions = [
-            Vec2::new(SCREEN_WIDTH / 3. - SCREEN_WIDTH / 2., -i as f32 * 100.),
-            Vec2::new(
-                2. * SCREEN_WIDTH / 3. - SCREEN_WIDTH / 2.,
-                -(i + 1) as f32 * 100.,
-            ),
-            Vec2::new(SCREEN_WIDTH / 2. + ENEMY_SIZE / 2., -i as f32 * 100.),
-        ];
-        enemies.push((
-            Vec2::new(-SCREEN_WIDTH / 2. - ENEMY_SIZE / 2., -i as f32 * 100.),
-            enemy_positions.into_iter().rev().collect::<Vec<_>>().into(),
-        ));
-    }
-
-    for i in 0..3 {
-        let enemy_positions = [
-            Vec2::new(
-                SCREEN_WIDTH / 3. + SCREEN_WIDTH / 2.,
-                -i as f32 * 100. - 400.,
-            ),
-            Vec2::new(
-                -SCREEN_WIDTH / 3. + SCREEN_WIDTH / 2.,
-                -i as f32 * 100. - 400.,
-            ),
-            Vec2::new(
-                -SCREEN_WIDTH / 2. - ENEMY_SIZE / 2.,
-                -i as f32 * 100. - 400.,
-            ),
-        ];
-        enemies.push((
-            Vec2::new(SCREEN_WIDTH / 2. + ENEMY_SIZE / 2., -i as f32 * 100. - 400.),
-            enemy_positions.into_iter().rev().collect::<Vec<_>>().into(),
-        ));
-    }
-
-    enemies.push((
-        Vec2::new(0., -900.),
-        vec![
-            Vec2::new(0., -800.),
-            Vec2::new(0., -750.),
-            Vec2::new(SCREEN_WIDTH / 3., -800.),
-            Vec2::new(SCREEN_WIDTH / 2. + ENEMY_SIZE / 2., -800.),
-        ]
-        .into_iter()
-        .rev()
-        .collect::<Vec<_>>()
-        .into(),
-    ));
-
-    let level = Level {
-        enemies,
-        boss_pos: Vec2::new(0., -1000.).into(),
-    };
-    commands.insert_resource(level);
-}
-
 #[derive(Component)]
 struct MainCamera;
 
@@ -355,6 +379,10 @@ fn scroll_background(
 #[derive(Resource)]
 struct GameOverTimer(Timer);
 
+/// Makes `GameState::GameOver` go straight back to `GameState::Main` instead of the title.
+#[derive(Resource)]
+struct RestartRequested;
+
 #[derive(Resource, Default, PartialEq)]
 struct MouseWorldPos(Vec2);
 
@@ -365,11 +393,13 @@ fn update_mouse_pos(
     // query to get the window (so we can read the current cursor position)
     q_window: Query<&Window, With<PrimaryWindow>>,
     // query to get camera transform
-    q_camera: Query<(&Camera, &GlobalTransform)>,
+    q_camera: Query<(&Camera, &Transform), With<MainCamera>>,
 ) {
     // get the camera info and transform
     // assuming there is exactly one main camera entity, so Query::single() is OK
     let (camera, camera_transform) = q_camera.single();
+    // The logical transform, without the camera effects
+    let camera_transform = GlobalTransform::from(*camera_transform);
 
     // There is only one primary window, so we can similarly get it from the query:
     let window = q_window.single();
@@ -378,7 +408,7 @@ fn update_mouse_pos(
     // then, ask bevy to convert into world coordinates, and truncate to discard Z
     if let Some(world_position) = window
         .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .and_then(|cursor| camera.viewport_to_world(&camera_transform, cursor))
         .map(|ray| ray.origin.truncate())
     {
         if let Some(mut mycoords) = mycoords {
@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    culling::Cull,
+    health::{DamageEvent, DamageKind, Died, HealthSet},
+    state_scoped::StateScoped,
+    GameState, MainCamera, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+/// Maximum number of particles alive at once. Emitters skip particles over the budget.
+const MAX_PARTICLES: usize = 2000;
+
+const PARTICLE_Z: f32 = 20.0;
+
+/// Height above the view the title rain starts at. The emitter follows the camera.
+const TITLE_RAIN_OFFSET: f32 = 20.0;
+
+/// Distance outside the view particles are culled at, enough for the title rain to fall in.
+const PARTICLE_CULL_MARGIN: f32 = TITLE_RAIN_OFFSET + 16.0;
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ParticleBudget(MAX_PARTICLES))
+            .add_systems(OnEnter(GameState::Title), spawn_title_rain)
+            .add_systems(Update, (emit_particles, update_particles).chain())
+            .add_systems(
+                PostUpdate,
+                (
+                    splash_on_hit.after(HealthSet::Damage),
+                    explode_on_death.in_set(HealthSet::Death),
+                ),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct ParticleBudget(pub usize);
+
+#[derive(Component, Clone, Debug)]
+pub struct ParticleEmitter {
+    /// Particles per second.
+    pub rate: f32,
+    /// Particles emitted at once when the emitter starts.
+    pub burst: u32,
+    /// Seconds the emitter runs. Emitters with a duration are despawned when it runs out.
+    pub duration: Option<f32>,
+    /// Half size of the rectangle particles are spawned in.
+    pub area: Vec2,
+    pub lifetime: f32,
+    pub direction: Vec2,
+    /// Half angle of the cone particles are shot in, in radians.
+    pub spread: f32,
+    pub speed: f32,
+    /// Random variation of the speed, as a fraction of it.
+    pub speed_variance: f32,
+    pub gravity: Vec2,
+    pub color: (Color, Color),
+    pub size: (Vec2, Vec2),
+    accumulator: f32,
+    elapsed: f32,
+    started: bool,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            rate: 0.0,
+            burst: 0,
+            duration: None,
+            area: Vec2::ZERO,
+            lifetime: 1.0,
+            direction: Vec2::Y,
+            spread: std::f32::consts::PI,
+            speed: 100.0,
+            speed_variance: 0.0,
+            gravity: Vec2::ZERO,
+            color: (Color::WHITE, Color::WHITE.with_a(0.)),
+            size: (Vec2::splat(4.), Vec2::splat(4.)),
+            accumulator: 0.0,
+            elapsed: 0.0,
+            started: false,
+        }
+    }
+}
+
+impl ParticleEmitter {
+    /// Water splash of a player bullet hitting something.
+    pub fn splash() -> Self {
+        Self {
+            burst: 8,
+            duration: Some(0.),
+            lifetime: 0.4,
+            speed: 150.,
+            speed_variance: 0.5,
+            gravity: Vec2::NEG_Y * 600.,
+            color: (Color::CYAN, Color::CYAN.with_a(0.)),
+            size: (Vec2::splat(4.), Vec2::splat(1.)),
+            ..default()
+        }
+    }
+
+    pub fn explosion() -> Self {
+        Self {
+            burst: 40,
+            duration: Some(0.),
+            area: Vec2::splat(10.),
+            lifetime: 0.6,
+            speed: 250.,
+            speed_variance: 0.7,
+            color: (Color::ORANGE, Color::RED.with_a(0.)),
+            size: (Vec2::splat(8.), Vec2::splat(2.)),
+            ..default()
+        }
+    }
+
+    pub fn boss_explosion() -> Self {
+        Self {
+            rate: 300.,
+            burst: 150,
+            duration: Some(1.5),
+            area: Vec2::splat(50.),
+            lifetime: 1.0,
+            speed: 300.,
+            speed_variance: 0.8,
+            color: (Color::YELLOW, Color::RED.with_a(0.)),
+            size: (Vec2::splat(12.), Vec2::splat(2.)),
+            ..default()
+        }
+    }
+
+    /// Rain falling over the whole screen width.
+    pub fn rain() -> Self {
+        Self {
+            rate: 150.,
+            area: Vec2::new(SCREEN_WIDTH / 2., 0.),
+            lifetime: 1.2,
+            direction: Vec2::new(-0.1, -1.),
+            spread: 0.,
+            speed: 700.,
+            speed_variance: 0.2,
+            color: (Color::CYAN.with_a(0.5), Color::CYAN.with_a(0.2)),
+            size: (Vec2::new(1.5, 16.), Vec2::new(1.5, 16.)),
+            ..default()
+        }
+    }
+}
+
+/// Emits particles at the entity's position when it dies.
+#[derive(Component)]
+pub struct ExplodeOnDeath(pub ParticleEmitter);
+
+#[derive(Component)]
+pub struct Particle {
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+    gravity: Vec2,
+    color: (Color, Color),
+    size: (Vec2, Vec2),
+}
+
+pub fn spawn_particles(commands: &mut Commands, emitter: ParticleEmitter, pos: Vec2) {
+    commands.spawn((
+        Name::new("ParticleEmitter"),
+        emitter,
+        TransformBundle::from_transform(Transform::from_translation(pos.extend(PARTICLE_Z))),
+        StateScoped(GameState::Main),
+    ));
+}
+
+fn emit_particles(
+    mut commands: Commands,
+    mut emitters: Query<(
+        Entity,
+        &mut ParticleEmitter,
+        &GlobalTransform,
+        Option<&StateScoped>,
+    )>,
+    particles: Query<(), With<Particle>>,
+    budget: Res<ParticleBudget>,
+    time: Res<Time<Virtual>>,
+) {
+    let dt = time.delta_seconds();
+    let mut rng = rand::thread_rng();
+    let mut alive = particles.iter().len();
+
+    for (id, mut emitter, transform, scoped) in &mut emitters {
+        let mut count = if emitter.started { 0 } else { emitter.burst };
+        emitter.started = true;
+        emitter.elapsed += dt;
+        emitter.accumulator += emitter.rate * dt;
+        count += emitter.accumulator as u32;
+        emitter.accumulator = emitter.accumulator.fract();
+
+        let center = transform.translation().xy();
+        for _ in 0..count {
+            if alive >= budget.0 {
+                break;
+            }
+            alive += 1;
+
+            let offset =
+                Vec2::new(rng.gen::<f32>() * 2. - 1., rng.gen::<f32>() * 2. - 1.) * emitter.area;
+            let angle = emitter.direction.y.atan2(emitter.direction.x)
+                + (rng.gen::<f32>() * 2. - 1.) * emitter.spread;
+            let speed =
+                emitter.speed * (1. + (rng.gen::<f32>() * 2. - 1.) * emitter.speed_variance);
+
+            let mut particle = commands.spawn((
+                Particle {
+                    velocity: Vec2::from_angle(angle) * speed,
+                    age: 0.,
+                    lifetime: emitter.lifetime,
+                    gravity: emitter.gravity,
+                    color: emitter.color,
+                    size: emitter.size,
+                },
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: emitter.color.0,
+                        custom_size: Some(emitter.size.0),
+                        ..default()
+                    },
+                    transform: Transform::from_translation((center + offset).extend(PARTICLE_Z)),
+                    ..default()
+                },
+                Cull {
+                    margin: PARTICLE_CULL_MARGIN,
+                },
+            ));
+            // Particles outlive their emitter but not its state.
+            if let Some(&scoped) = scoped {
+                particle.insert(scoped);
+            }
+        }
+
+        if emitter
+            .duration
+            .is_some_and(|duration| emitter.elapsed >= duration)
+        {
+            commands.entity(id).despawn_recursive();
+        }
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    time: Res<Time<Virtual>>,
+) {
+    let dt = time.delta_seconds();
+
+    for (id, mut particle, mut transform, mut sprite) in &mut particles {
+        particle.age += dt;
+        if particle.age >= particle.lifetime {
+            commands.entity(id).despawn();
+            continue;
+        }
+
+        let gravity = particle.gravity;
+        particle.velocity += gravity * dt;
+        transform.translation += (particle.velocity * dt).extend(0.);
+
+        let t = particle.age / particle.lifetime;
+        sprite.color = lerp_color(particle.color.0, particle.color.1, t);
+        sprite.custom_size = Some(particle.size.0.lerp(particle.size.1, t));
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let a = Vec4::from(a.as_rgba_f32());
+    let b = Vec4::from(b.as_rgba_f32());
+    Color::from(a.lerp(b, t))
+}
+
+fn splash_on_hit(mut commands: Commands, mut damage_events: EventReader<DamageEvent>) {
+    for ev in damage_events.read() {
+        let Some(pos) = ev.position else {
+            continue;
+        };
+        if matches!(ev.kind, DamageKind::Water | DamageKind::Charged) {
+            spawn_particles(&mut commands, ParticleEmitter::splash(), pos);
+        }
+    }
+}
+
+fn explode_on_death(
+    mut commands: Commands,
+    mut died_events: EventReader<Died>,
+    q: Query<(&ExplodeOnDeath, &GlobalTransform)>,
+) {
+    for ev in died_events.read() {
+        if let Ok((ExplodeOnDeath(emitter), transform)) = q.get(ev.entity) {
+            spawn_particles(&mut commands, emitter.clone(), transform.translation().xy());
+        }
+    }
+}
+
+// Follows the camera as it scrolls down the title screen.
+fn spawn_title_rain(mut commands: Commands, camera: Query<Entity, With<MainCamera>>) {
+    commands.entity(camera.single()).with_children(|c| {
+        c.spawn((
+            Name::new("TitleRain"),
+            ParticleEmitter::rain(),
+            TransformBundle::from_transform(Transform::from_xyz(
+                0.,
+                SCREEN_HEIGHT / 2. + TITLE_RAIN_OFFSET,
+                0.,
+            )),
+            StateScoped(GameState::Title),
+        ));
+    });
+}
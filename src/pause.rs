@@ -0,0 +1,221 @@
+use bevy::{
+    prelude::*,
+    window::{CursorLeft, WindowFocused},
+};
+use bevy_tweening::{Animator, AnimatorState};
+use bevy_xpbd_2d::prelude::*;
+
+use crate::{
+    audio::{Sound, SoundEvent},
+    settings::{ControlScheme, Settings},
+    GameOverTimer, GameState, RestartRequested,
+};
+
+const BUTTON_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const BUTTON_HOVERED_COLOR: Color = Color::rgba(0.0, 0.5, 0.5, 0.6);
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<PauseState>()
+            .add_systems(OnEnter(PauseState::Paused), (pause_time, spawn_pause_menu))
+            .add_systems(OnEnter(PauseState::Continue), pause_time)
+            .add_systems(OnExit(PauseState::Continue), unpause_time)
+            .add_systems(
+                OnExit(PauseState::Paused),
+                (unpause_time, despawn_pause_menu),
+            )
+            .add_systems(
+                Update,
+                (toggle_pause, auto_pause).run_if(in_state(GameState::Main)),
+            )
+            .add_systems(
+                Update,
+                pause_menu_buttons.run_if(in_state(PauseState::Paused)),
+            )
+            .add_systems(
+                OnExit(GameState::Main),
+                |mut state: ResMut<NextState<PauseState>>| {
+                    state.set(PauseState::Running);
+                },
+            );
+    }
+}
+
+/// Whether the gameplay in `GameState::Main` is running or paused.
+#[derive(Debug, States, Default, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+    /// Waiting for the continue prompt after losing the last life.
+    Continue,
+}
+
+fn pause_time(
+    mut time: ResMut<Time<Virtual>>,
+    mut physics_time: ResMut<Time<Physics>>,
+    mut animators: Query<&mut Animator<Transform>>,
+) {
+    time.pause();
+    physics_time.pause();
+    for mut animator in &mut animators {
+        animator.state = AnimatorState::Paused;
+    }
+}
+
+fn unpause_time(
+    mut time: ResMut<Time<Virtual>>,
+    mut physics_time: ResMut<Time<Physics>>,
+    mut animators: Query<&mut Animator<Transform>>,
+) {
+    time.unpause();
+    physics_time.unpause();
+    for mut animator in &mut animators {
+        animator.state = AnimatorState::Playing;
+    }
+}
+
+fn toggle_pause(
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        match state.get() {
+            PauseState::Running => next_state.set(PauseState::Paused),
+            PauseState::Paused => next_state.set(PauseState::Running),
+            PauseState::Continue => {}
+        }
+    }
+}
+
+// Pause when the window loses focus or the cursor (which controls the player) leaves it.
+fn auto_pause(
+    mut focused: EventReader<WindowFocused>,
+    mut cursor_left: EventReader<CursorLeft>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+    settings: Res<Settings>,
+) {
+    if *state.get() != PauseState::Running {
+        return;
+    }
+    let lost_focus = focused.read().any(|ev| !ev.focused);
+    let left = cursor_left.read().count() > 0 && settings.controls.scheme == ControlScheme::Mouse;
+    if lost_focus || left {
+        next_state.set(PauseState::Paused);
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Clone, Copy)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Title,
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Pause menu"),
+            PauseMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.),
+                    ..default()
+                },
+                background_color: Color::BLACK.with_a(0.4).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn(TextBundle::from_section(
+                "PAUSED",
+                TextStyle {
+                    font_size: 48.,
+                    color: Color::CYAN,
+                    ..default()
+                },
+            ));
+
+            for (button, label) in [
+                (PauseButton::Resume, "Resume"),
+                (PauseButton::Restart, "Restart"),
+                (PauseButton::Title, "Title"),
+            ] {
+                c.spawn((
+                    button,
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.),
+                            height: Val::Px(40.),
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    },
+                ))
+                .with_children(|c| {
+                    c.spawn(TextBundle::from_section(
+                        label,
+                        TextStyle {
+                            font_size: 24.,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ));
+                });
+            }
+        });
+}
+
+fn despawn_pause_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenu>>) {
+    for e in &menu {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn pause_menu_buttons(
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &PauseButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut sounds: EventWriter<SoundEvent>,
+) {
+    for (interaction, button, mut color) in &mut buttons {
+        match interaction {
+            Interaction::Pressed => {
+                sounds.send(SoundEvent(Sound::MenuSelect));
+                pause_state.set(PauseState::Running);
+
+                if let PauseButton::Restart | PauseButton::Title = button {
+                    // Leave through GameOver so that its cleanup runs.
+                    if let PauseButton::Restart = button {
+                        commands.insert_resource(RestartRequested);
+                    }
+                    commands
+                        .insert_resource(GameOverTimer(Timer::from_seconds(0., TimerMode::Once)));
+                    game_state.set(GameState::GameOver);
+                }
+            }
+            Interaction::Hovered => {
+                sounds.send(SoundEvent(Sound::MenuMove));
+                *color = BUTTON_HOVERED_COLOR.into();
+            }
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+}
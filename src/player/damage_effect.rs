@@ -3,6 +3,7 @@ use bevy::{
     render::{mesh::Indices, render_resource::PrimitiveTopology},
     sprite::Mesh2dHandle,
 };
+#[cfg(feature = "dev")]
 use bevy_debug_text_overlay::screen_print;
 
 use crate::{damage::BossDiedEvent, MainCamera, SCREEN_HEIGHT, SCREEN_WIDTH};
@@ -1,28 +1,52 @@
 mod damage_effect;
 
 use bevy::{prelude::*, sprite::Mesh2dHandle};
+#[cfg(feature = "dev")]
 use bevy_debug_text_overlay::screen_print;
 use bevy_xpbd_2d::prelude::*;
 use leafwing_input_manager::prelude::*;
 use rand::Rng;
 
-use crate::{damage::BossDiedEvent, item::Item, MainCamera, MyLayer};
+use crate::{
+    audio::{DeathSound, Sound, SoundEvent},
+    boss::Boss,
+    bullet_collision::{BulletCollider, Hurtbox, HurtboxShape, Team},
+    bullet_pool::{BulletKind, BulletPool, PooledBullet},
+    culling::Cull,
+    damage::{BossDiedEvent, Critical, Damage},
+    enemy::Enemy,
+    health::{DamageKind, DespawnOnDeath, Died, HealthSet},
+    item::{Item, ItemCollectedEvent},
+    pause::PauseState,
+    score::{Score, ITEM_SCORE},
+    settings::{ControlScheme, Settings},
+    GameState, MainCamera, MouseWorldPos, MyLayer, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
 
 const PLAYER_BULLET_SIZE: f32 = 6.0;
 
+const PLAYER_SPEED: f32 = 300.0;
+
+const PLAYER_BULLET_DAMAGE: f32 = 2.0;
+
+const BULLET_GRAVITY: Vec2 = Vec2::new(0., -300.);
+
+/// The player can't attack below this radius.
+pub const MIN_ATTACK_RADIUS: f32 = 5.0;
+
 #[derive(Resource)]
 struct PlayerResource {
     bullet_mesh: Handle<Mesh>,
+    charged_bullet_mesh: Handle<Mesh>,
     bullet_material: Handle<ColorMaterial>,
-    attack_sound: Handle<AudioSource>,
-    die_sound: Handle<AudioSource>,
+    indicator_mesh: Handle<Mesh>,
+    indicator_material: Handle<ColorMaterial>,
 }
 
 fn startup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut color_materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
 ) {
     commands.insert_resource(PlayerResource {
         bullet_mesh: meshes.add(
@@ -32,9 +56,24 @@ fn startup(
             }
             .into(),
         ),
+        // Unit circle scaled by the charge level
+        charged_bullet_mesh: meshes.add(
+            shape::Circle {
+                radius: 1.,
+                vertices: 12,
+            }
+            .into(),
+        ),
         bullet_material: color_materials.add(ColorMaterial::from(Color::CYAN)),
-        attack_sound: asset_server.load("sounds/splash_03.ogg"),
-        die_sound: asset_server.load("sounds/hit_01.ogg"),
+        // Unit circle scaled by the charge
+        indicator_mesh: meshes.add(
+            shape::Circle {
+                radius: 1.,
+                vertices: 32,
+            }
+            .into(),
+        ),
+        indicator_material: color_materials.add(ColorMaterial::from(Color::WHITE.with_a(0.3))),
     });
 }
 
@@ -60,6 +99,29 @@ impl Player {
 enum Action {
     Attack,
     Dodge,
+    SwitchShot,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+}
+
+fn input_map(settings: &Settings) -> InputMap<Action> {
+    let controls = &settings.controls;
+    let mut bindings: Vec<(UserInput, Action)> = vec![
+        (controls.attack.into(), Action::Attack),
+        (controls.dodge.into(), Action::Dodge),
+        (controls.switch_shot.into(), Action::SwitchShot),
+    ];
+    if controls.scheme == ControlScheme::Keyboard {
+        bindings.extend([
+            (controls.move_up.into(), Action::MoveUp),
+            (controls.move_down.into(), Action::MoveDown),
+            (controls.move_left.into(), Action::MoveLeft),
+            (controls.move_right.into(), Action::MoveRight),
+        ]);
+    }
+    InputMap::new(bindings)
 }
 
 fn player_spawn(
@@ -67,6 +129,8 @@ fn player_spawn(
     q: Query<(Entity, &Player, Option<&Transform>), Added<Player>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut color_materials: ResMut<Assets<ColorMaterial>>,
+    res: Res<PlayerResource>,
+    settings: Res<Settings>,
 ) {
     for (entity, player, transform) in q.iter() {
         commands
@@ -86,20 +150,28 @@ fn player_spawn(
                 ..default()
             })
             .insert(InputManagerBundle::<Action> {
-                input_map: InputMap::new([
-                    (MouseButton::Left, Action::Attack),
-                    (MouseButton::Right, Action::Dodge),
-                ]),
+                input_map: input_map(&settings),
                 ..default()
             })
             .insert((
                 RigidBody::Kinematic,
-                CollisionLayers::new(
-                    [MyLayer::Player],
-                    [MyLayer::Enemy, MyLayer::EnemyBullet, MyLayer::Item],
-                ),
+                CollisionLayers::new([MyLayer::Player], [MyLayer::Enemy, MyLayer::Item]),
                 // Collider is added in update_player_radius
-            ));
+            ))
+            .insert((DeathSound(Sound::PlayerDie), DespawnOnDeath))
+            .insert((Charge::default(), ShotType::default()))
+            .with_children(|c| {
+                c.spawn((
+                    ChargeIndicator,
+                    ColorMesh2dBundle {
+                        mesh: Mesh2dHandle(res.indicator_mesh.clone()),
+                        material: res.indicator_material.clone(),
+                        transform: Transform::from_xyz(0., 0., -0.1),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                ));
+            });
     }
 }
 
@@ -120,81 +192,408 @@ fn update_player_radius(
                     .into(),
                 ),
             ))
-            .insert(Collider::ball(player.radius * 0.8));
+            .insert((
+                Collider::ball(player.radius * 0.8),
+                Hurtbox {
+                    shape: HurtboxShape::Circle(player.radius * 0.8),
+                    team: Team::Player,
+                },
+            ));
     }
 }
 
+// Moves the player with the movement keys, keeping its position on the screen while scrolling.
+#[allow(clippy::type_complexity)]
+fn keyboard_movement(
+    settings: Res<Settings>,
+    mut player: Query<
+        (Entity, &ActionState<Action>, &mut Transform),
+        (With<Player>, Without<MainCamera>),
+    >,
+    camera: Query<&Transform, With<MainCamera>>,
+    mut last_camera: Local<Option<(Entity, Vec2)>>,
+    time: Res<Time<Virtual>>,
+) {
+    if settings.controls.scheme != ControlScheme::Keyboard {
+        return;
+    }
+    let Ok((id, state, mut transform)) = player.get_single_mut() else {
+        return;
+    };
+    let camera = camera.single().translation.xy();
+
+    let scroll = match *last_camera {
+        Some((last_id, last_camera)) if last_id == id => camera - last_camera,
+        _ => Vec2::ZERO,
+    };
+    *last_camera = Some((id, camera));
+
+    let mut direction = Vec2::ZERO;
+    if state.pressed(Action::MoveUp) {
+        direction.y += 1.;
+    }
+    if state.pressed(Action::MoveDown) {
+        direction.y -= 1.;
+    }
+    if state.pressed(Action::MoveLeft) {
+        direction.x -= 1.;
+    }
+    if state.pressed(Action::MoveRight) {
+        direction.x += 1.;
+    }
+
+    let half_screen = Vec2::new(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.;
+    let pos = (transform.translation.xy()
+        + scroll
+        + direction.normalize_or_zero() * PLAYER_SPEED * time.delta_seconds())
+    .clamp(camera - half_screen, camera + half_screen);
+    transform.translation = pos.extend(transform.translation.z);
+}
+
 #[derive(Component)]
 pub struct PlayerBullet;
 
+/// Movement of player bullets, which don't have physics bodies.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct BulletVelocity {
+    pub linear: Vec2,
+    /// Multiplier of [`BULLET_GRAVITY`].
+    pub gravity_scale: f32,
+}
+
+impl BulletVelocity {
+    fn new(linear: Vec2) -> Self {
+        Self {
+            linear,
+            gravity_scale: 1.,
+        }
+    }
+
+    fn without_gravity(self) -> Self {
+        Self {
+            gravity_scale: 0.,
+            ..self
+        }
+    }
+}
+
+fn move_bullets(
+    mut bullets: Query<(&mut Transform, &mut BulletVelocity, &PooledBullet)>,
+    time: Res<Time<Virtual>>,
+) {
+    let dt = time.delta_seconds();
+    for (mut transform, mut velocity, pooled) in &mut bullets {
+        if !pooled.is_active() {
+            continue;
+        }
+        let BulletVelocity {
+            linear,
+            gravity_scale,
+        } = &mut *velocity;
+        *linear += BULLET_GRAVITY * *gravity_scale * dt;
+        transform.translation += (*linear * dt).extend(0.);
+    }
+}
+
+/// Attack held for less than this many seconds is a tap firing the scatter burst.
+const CHARGE_THRESHOLD: f32 = 0.25;
+
+/// Seconds of holding Attack for a full charge.
+const MAX_CHARGE_SECS: f32 = 1.5;
+
+const CHARGED_SHOT_SPEED: f32 = 500.0;
+
+#[derive(Component, Default)]
+struct Charge {
+    /// Seconds Attack has been held.
+    held: f32,
+}
+
+impl Charge {
+    fn is_charging(&self) -> bool {
+        self.held >= CHARGE_THRESHOLD
+    }
+
+    /// Charge from 0 to 1.
+    fn level(&self) -> f32 {
+        ((self.held - CHARGE_THRESHOLD) / (MAX_CHARGE_SECS - CHARGE_THRESHOLD)).clamp(0., 1.)
+    }
+}
+
+#[derive(Component)]
+struct ChargeIndicator;
+
+/// Bullet pattern fired by tapping Attack.
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ShotType {
+    /// Bullets splashing out in all directions.
+    #[default]
+    Splash,
+    /// A fan of bullets toward the aim direction.
+    Cone,
+    /// A narrow straight stream toward the aim direction.
+    Focused,
+}
+
+impl ShotType {
+    pub fn name(self) -> &'static str {
+        match self {
+            ShotType::Splash => "Splash",
+            ShotType::Cone => "Cone",
+            ShotType::Focused => "Focused",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            ShotType::Splash => ShotType::Cone,
+            ShotType::Cone => ShotType::Focused,
+            ShotType::Focused => ShotType::Splash,
+        }
+    }
+
+    /// Number of bullets fired at the given radius.
+    fn bullet_count(self, radius: f32) -> usize {
+        let (full, min) = match self {
+            ShotType::Splash => (64., 0),
+            ShotType::Cone => (24., 3),
+            ShotType::Focused => (12., 2),
+        };
+        ((radius / 50. * full) as usize).max(min)
+    }
+}
+
+const CONE_HALF_ANGLE: f32 = 0.4;
+
+const AIMED_SHOT_SPEED: f32 = 350.0;
+
+fn switch_shot_system(mut q: Query<(&ActionState<Action>, &mut ShotType)>) {
+    for (state, mut shot_type) in &mut q {
+        if state.just_pressed(Action::SwitchShot) {
+            *shot_type = shot_type.next();
+        }
+    }
+}
+
+// Fires when Attack is released: the selected shot after a tap, a charged stream after holding.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn attack_system(
     mut commands: Commands,
-    mut q: Query<
-        (Entity, &ActionState<Action>, &Transform, &mut Player),
-        (With<Player>, Changed<ActionState<Action>>),
-    >,
+    mut q: Query<(
+        &ActionState<Action>,
+        &Transform,
+        &mut Player,
+        &mut Charge,
+        &ShotType,
+    )>,
+    targets: Query<&Transform, (Or<(With<Enemy>, With<Boss>)>, Without<Player>)>,
+    mouse_pos: Option<Res<MouseWorldPos>>,
+    settings: Res<Settings>,
     res: Res<PlayerResource>,
+    mut pool: ResMut<BulletPool>,
+    mut sounds: EventWriter<SoundEvent>,
+    time: Res<Time<Virtual>>,
 ) {
-    for (id, state, transform, mut player) in &mut q {
-        if player.radius < 5. {
+    for (state, transform, mut player, mut charge, &shot_type) in &mut q {
+        if state.pressed(Action::Attack) {
+            charge.held += time.delta_seconds();
+            continue;
+        }
+        if !state.just_released(Action::Attack) {
             continue;
         }
+        let charge = std::mem::take(&mut *charge);
 
-        if state.just_pressed(Action::Attack) {
-            commands.entity(id).try_insert(AudioBundle {
-                source: res.attack_sound.clone(),
-                settings: PlaybackSettings {
-                    mode: bevy::audio::PlaybackMode::Remove,
-                    ..default()
-                },
-            });
+        if player.radius < MIN_ATTACK_RADIUS {
+            continue;
+        }
 
-            let bullet_bundle = ColorMesh2dBundle {
-                mesh: Mesh2dHandle(res.bullet_mesh.clone()),
-                material: res.bullet_material.clone(),
-                ..default()
-            };
+        sounds.send(SoundEvent(Sound::PlayerShot));
+
+        // The player follows the cursor with mouse controls, so only aim with it on keyboard.
+        let pos = transform.translation.xy();
+        let cursor = mouse_pos
+            .as_ref()
+            .filter(|_| settings.controls.scheme == ControlScheme::Keyboard)
+            .and_then(|mouse_pos| (mouse_pos.0 - pos).try_normalize());
+        let direction = cursor
+            .or_else(|| {
+                targets
+                    .iter()
+                    .map(|t| t.translation.xy() - pos)
+                    .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+                    .and_then(|d| d.try_normalize())
+            })
+            .unwrap_or(Vec2::NEG_Y);
+
+        if charge.is_charging() {
+            fire_charged(
+                &mut commands,
+                &mut pool,
+                transform,
+                &mut player,
+                &res,
+                direction,
+                charge.level(),
+            );
+            continue;
+        }
+
+        let num = shot_type.bullet_count(player.radius);
+        player.increase(-100.);
+        // screen_print!("Player radius: {}", player.radius);
 
-            let num = (player.radius / 50. * 64.) as usize;
-
-            player.increase(-100.);
-            // screen_print!("Player radius: {}", player.radius);
-
-            let mut rng = rand::thread_rng();
-            for _ in 0..num {
-                let r = rng.gen::<f32>() * 50.0;
-                let theta = rng.gen::<f32>() * std::f32::consts::PI * 2.0;
-                let pos = Vec2::new(theta.cos(), theta.sin()) * r;
-
-                commands
-                    .spawn(ColorMesh2dBundle {
-                        transform: Transform::from_translation(
-                            transform.translation + pos.extend(0.0),
-                        ),
-                        ..bullet_bundle.clone()
-                    })
-                    .insert((
-                        PlayerBullet,
-                        Name::new("PlayerBullet"),
-                        RigidBody::Dynamic,
-                        Collider::ball(PLAYER_BULLET_SIZE),
-                        CollisionLayers::new([MyLayer::PlayerBullet], [MyLayer::Enemy]),
-                        LinearVelocity(pos * 2.0),
-                    ));
+        let mut rng = rand::thread_rng();
+        match shot_type {
+            ShotType::Splash => {
+                for _ in 0..num {
+                    let r = rng.gen::<f32>() * 50.0;
+                    let theta = rng.gen::<f32>() * std::f32::consts::PI * 2.0;
+                    let pos = Vec2::new(theta.cos(), theta.sin()) * r;
+
+                    spawn_bullet(
+                        &mut commands,
+                        &mut pool,
+                        &res,
+                        transform.translation + pos.extend(0.0),
+                        BulletVelocity::new(pos * 2.0),
+                    );
+                }
+            }
+            ShotType::Cone => {
+                let base = direction.y.atan2(direction.x);
+                for i in 0..num {
+                    let t = i as f32 / (num - 1) as f32 * 2. - 1.;
+                    let dir = Vec2::from_angle(base + t * CONE_HALF_ANGLE);
+                    let speed = AIMED_SHOT_SPEED * (0.9 + rng.gen::<f32>() * 0.2);
+
+                    spawn_bullet(
+                        &mut commands,
+                        &mut pool,
+                        &res,
+                        transform.translation + (dir * 10.).extend(0.),
+                        BulletVelocity::new(dir * speed),
+                    );
+                }
+            }
+            ShotType::Focused => {
+                for i in 0..num {
+                    let pos = direction * (10. - i as f32 * PLAYER_BULLET_SIZE * 2.);
+
+                    spawn_bullet(
+                        &mut commands,
+                        &mut pool,
+                        &res,
+                        transform.translation + pos.extend(0.),
+                        BulletVelocity::new(direction * AIMED_SHOT_SPEED * 1.5).without_gravity(),
+                    );
+                }
             }
         }
     }
 }
 
-fn remove_bullets(
-    mut commands: Commands,
-    bullets: Query<(Entity, &Transform), With<PlayerBullet>>,
-    camera: Query<&Transform, With<MainCamera>>,
+fn spawn_bullet(
+    commands: &mut Commands,
+    pool: &mut BulletPool,
+    res: &PlayerResource,
+    pos: Vec3,
+    velocity: BulletVelocity,
+) {
+    pool.acquire(commands, BulletKind::Player)
+        .insert(ColorMesh2dBundle {
+            mesh: Mesh2dHandle(res.bullet_mesh.clone()),
+            material: res.bullet_material.clone(),
+            transform: Transform::from_translation(pos),
+            ..default()
+        })
+        .insert((
+            PlayerBullet,
+            Name::new("PlayerBullet"),
+            BulletCollider::new(PLAYER_BULLET_SIZE, Team::Player),
+            velocity,
+            Cull {
+                margin: PLAYER_BULLET_SIZE,
+            },
+        ))
+        .insert((
+            Damage {
+                amount: PLAYER_BULLET_DAMAGE,
+                kind: DamageKind::Water,
+            },
+            Critical {
+                chance: 0.05,
+                multiplier: 2.0,
+            },
+        ));
+}
+
+// A straight line of large bullets. Size, damage and cost grow with the charge level.
+fn fire_charged(
+    commands: &mut Commands,
+    pool: &mut BulletPool,
+    transform: &Transform,
+    player: &mut Player,
+    res: &PlayerResource,
+    direction: Vec2,
+    level: f32,
+) {
+    let size = PLAYER_BULLET_SIZE * (1.5 + level * 1.5);
+    let num = 6 + (level * 10.) as usize;
+
+    let start = transform.translation.xy() + direction * player.radius;
+    player.increase(-(100. + level * 300.));
+
+    for i in 0..num {
+        // Bullets further back trail behind the head of the stream
+        let pos = start - direction * i as f32 * size * 1.5;
+
+        pool.acquire(commands, BulletKind::Player)
+            .insert(ColorMesh2dBundle {
+                mesh: Mesh2dHandle(res.charged_bullet_mesh.clone()),
+                material: res.bullet_material.clone(),
+                transform: Transform::from_translation(pos.extend(transform.translation.z))
+                    .with_scale(Vec3::splat(size)),
+                ..default()
+            })
+            .insert((
+                PlayerBullet,
+                Name::new("PlayerBullet"),
+                BulletCollider::new(size, Team::Player),
+                BulletVelocity::new(direction * CHARGED_SHOT_SPEED).without_gravity(),
+                // The tail may start off-screen and catches up with the head.
+                Cull {
+                    margin: size + start.distance(pos),
+                },
+            ))
+            .insert((
+                Damage {
+                    amount: PLAYER_BULLET_DAMAGE * (2. + level * 3.),
+                    kind: DamageKind::Charged,
+                },
+                Critical {
+                    chance: 0.05,
+                    multiplier: 2.0,
+                },
+            ));
+    }
+}
+
+fn update_charge_indicator(
+    players: Query<(&Player, &Charge, &Children)>,
+    mut indicators: Query<(&mut Transform, &mut Visibility), With<ChargeIndicator>>,
 ) {
-    let camera = camera.single();
-    for (entity, transform) in &bullets {
-        if (transform.translation - camera.translation).length() > 1000.0 {
-            commands.entity(entity).despawn_recursive();
+    for (player, charge, children) in &players {
+        for &child in children {
+            let Ok((mut transform, mut vis)) = indicators.get_mut(child) else {
+                continue;
+            };
+            if charge.is_charging() {
+                *vis = Visibility::Inherited;
+                transform.scale = Vec3::splat(player.radius + 4. + charge.level() * 16.);
+            } else {
+                *vis = Visibility::Hidden;
+            }
         }
     }
 }
@@ -204,6 +603,8 @@ fn player_item_system(
     mut commands: Commands,
     mut player: Query<(&mut Player, &CollidingEntities)>,
     items: Query<&Item>,
+    mut score: ResMut<Score>,
+    mut collected: EventWriter<ItemCollectedEvent>,
 ) {
     let Ok((mut player, collisions)) = player.get_single_mut() else {
         return;
@@ -211,30 +612,41 @@ fn player_item_system(
     for &collision in collisions.iter() {
         if items.contains(collision) {
             player.increase(10.);
+            score.0 += ITEM_SCORE;
+            collected.send(ItemCollectedEvent);
             commands.entity(collision).despawn_recursive();
         }
     }
 }
 
 #[derive(Event)]
-pub struct PlayerDiedEvent;
+pub struct PlayerDiedEvent {
+    pub position: Vec2,
+}
 
-fn player_die_check(
-    mut commands: Commands,
-    player: Query<(Entity, &Player)>,
-    mut player_died_event: EventWriter<PlayerDiedEvent>,
-    player_res: Res<PlayerResource>,
-) {
+fn player_die_check(player: Query<(Entity, &Player)>, mut died_events: EventWriter<Died>) {
     let Ok((id, player)) = player.get_single() else {
         return;
     };
     if player.radius < 5. {
-        player_died_event.send(PlayerDiedEvent);
-        commands.spawn(AudioBundle {
-            source: player_res.die_sound.clone(),
-            settings: PlaybackSettings::DESPAWN,
+        died_events.send(Died {
+            entity: id,
+            killer: None,
         });
-        commands.entity(id).despawn_recursive();
+    }
+}
+
+fn player_died(
+    mut died_events: EventReader<Died>,
+    player: Query<&Transform, With<Player>>,
+    mut player_died_event: EventWriter<PlayerDiedEvent>,
+) {
+    for ev in died_events.read() {
+        if let Ok(transform) = player.get(ev.entity) {
+            player_died_event.send(PlayerDiedEvent {
+                position: transform.translation.xy(),
+            });
+        }
     }
 }
 
@@ -247,10 +659,21 @@ impl Plugin for PlayerPlugin {
         app.add_plugins(InputManagerPlugin::<Action>::default());
         app.add_systems(Startup, startup);
         app.add_systems(PostUpdate, (player_spawn, update_player_radius));
-        app.add_systems(Update, (attack_system, remove_bullets));
+        app.add_systems(
+            Update,
+            (switch_shot_system, attack_system, update_charge_indicator)
+                .run_if(in_state(PauseState::Running)),
+        );
+        app.add_systems(
+            Update,
+            keyboard_movement
+                .run_if(in_state(GameState::Main).and_then(in_state(PauseState::Running))),
+        );
+        app.add_systems(Update, move_bullets);
         app.add_systems(Update, player_item_system);
         app.add_event::<PlayerDiedEvent>()
-            .add_systems(Update, player_die_check);
+            .add_systems(Update, player_die_check)
+            .add_systems(PostUpdate, player_died.in_set(HealthSet::Death));
         app.add_systems(
             PostUpdate,
             (|mut player: Query<&mut Player>| {
@@ -261,6 +684,5 @@ impl Plugin for PlayerPlugin {
             })
             .run_if(on_event::<BossDiedEvent>()),
         );
-        app.insert_resource(Gravity(Vec2::NEG_Y * 300.0));
     }
 }
@@ -0,0 +1,117 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    difficulty::Difficulty,
+    enemy::EnemyEscapedEvent,
+    health::{Died, HealthSet},
+    title::GameMode,
+    GameState,
+};
+
+const HIGH_SCORES_PATH: &str = "highscores.ron";
+
+pub const ENEMY_SCORE: u32 = 100;
+pub const BOSS_SCORE: u32 = 5000;
+pub const ITEM_SCORE: u32 = 10;
+/// Points lost for each enemy leaving the screen alive.
+pub const ESCAPE_PENALTY: u32 = 50;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .insert_resource(HighScores::load())
+            .add_systems(OnEnter(GameState::Main), |mut score: ResMut<Score>| {
+                score.0 = 0;
+            })
+            .add_systems(OnEnter(GameState::GameOver), record_high_score)
+            .add_systems(PostUpdate, score_on_death.in_set(HealthSet::Death))
+            .add_systems(
+                Update,
+                (|mut escaped: EventReader<EnemyEscapedEvent>, mut score: ResMut<Score>| {
+                    let penalty = ESCAPE_PENALTY * escaped.read().count() as u32;
+                    score.0 = score.0.saturating_sub(penalty);
+                })
+                .run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+/// Score of the current run.
+#[derive(Resource, Default, Debug)]
+pub struct Score(pub u32);
+
+/// Points awarded when the entity dies.
+#[derive(Component)]
+pub struct ScoreValue(pub u32);
+
+fn score_on_death(
+    mut died_events: EventReader<Died>,
+    q: Query<&ScoreValue>,
+    mut score: ResMut<Score>,
+) {
+    for ev in died_events.read() {
+        // Only kills by the player's shots count, not e.g. the debug `kill` command.
+        if ev.killer.is_none() {
+            continue;
+        }
+        if let Ok(ScoreValue(value)) = q.get(ev.entity) {
+            score.0 += value;
+        }
+    }
+}
+
+/// Best score for each difficulty, persisted to [`HIGH_SCORES_PATH`].
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct HighScores(HashMap<Difficulty, u32>);
+
+impl HighScores {
+    fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(HIGH_SCORES_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&text).unwrap_or_else(|e| {
+            warn!("Failed to parse {HIGH_SCORES_PATH}: {e}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) {
+        let text = match ron::ser::to_string_pretty(self, default()) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Failed to serialize high scores: {e}");
+                return;
+            }
+        };
+        if let Err(e) = std::fs::write(HIGH_SCORES_PATH, text) {
+            warn!("Failed to write {HIGH_SCORES_PATH}: {e}");
+        }
+    }
+
+    pub fn get(&self, difficulty: Difficulty) -> u32 {
+        self.0.get(&difficulty).copied().unwrap_or(0)
+    }
+
+    /// Records the score if it beats the best one.
+    pub fn submit(&mut self, difficulty: Difficulty, score: u32) {
+        if score > self.get(difficulty) {
+            self.0.insert(difficulty, score);
+            self.save();
+        }
+    }
+}
+
+fn record_high_score(
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    mut high_scores: ResMut<HighScores>,
+    mode: Res<GameMode>,
+) {
+    if *mode == GameMode::Practice {
+        return;
+    }
+    high_scores.submit(*difficulty, score.0);
+}
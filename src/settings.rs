@@ -0,0 +1,581 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_framepace::{FramepaceSettings, Limiter};
+use leafwing_input_manager::user_input::UserInput;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::{Sound, SoundEvent},
+    GameState,
+};
+
+const SETTINGS_PATH: &str = "settings.ron";
+
+const BUTTON_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const BUTTON_HOVERED_COLOR: Color = Color::rgba(0.0, 0.5, 0.5, 0.6);
+
+const WINDOW_SCALES: [f32; 5] = [1.0, 1.25, 1.5, 1.75, 2.0];
+
+const FRAME_LIMITS: [FrameLimit; 6] = [
+    FrameLimit::Auto,
+    FrameLimit::Fps(30),
+    FrameLimit::Fps(60),
+    FrameLimit::Fps(120),
+    FrameLimit::Fps(144),
+    FrameLimit::Off,
+];
+
+const LIVES: [u32; 6] = [0, 1, 2, 3, 4, 5];
+
+/// Expects the [`Settings`] to be inserted already, `main` loads them to create the window.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<SettingsMenuState>()
+            .add_systems(
+                Update,
+                apply_settings.run_if(resource_changed::<Settings>()),
+            )
+            .add_systems(OnEnter(SettingsMenuState::Open), spawn_settings_menu)
+            .add_systems(
+                OnExit(SettingsMenuState::Open),
+                (despawn_settings_menu, save_settings),
+            )
+            .add_systems(
+                Update,
+                // Rebinding is inserted and removed through commands, the texts need to see it.
+                (
+                    settings_menu_buttons,
+                    apply_deferred,
+                    rebind_system,
+                    apply_deferred,
+                    update_settings_text,
+                )
+                    .chain()
+                    .run_if(in_state(SettingsMenuState::Open)),
+            )
+            .add_systems(
+                OnExit(GameState::Title),
+                |mut state: ResMut<NextState<SettingsMenuState>>| {
+                    state.set(SettingsMenuState::Closed);
+                },
+            );
+    }
+}
+
+/// Whether the settings screen is shown on top of the title.
+#[derive(Debug, States, Default, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum SettingsMenuState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// User settings, persisted to [`SETTINGS_PATH`].
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub window_scale: f32,
+    pub frame_limit: FrameLimit,
+    /// Extra lives at the start of a run.
+    pub lives: u32,
+    pub controls: Controls,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            music_volume: 1.0,
+            window_scale: 1.0,
+            frame_limit: FrameLimit::Auto,
+            lives: 2,
+            controls: Controls::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(SETTINGS_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&text).unwrap_or_else(|e| {
+            warn!("Failed to parse {SETTINGS_PATH}: {e}");
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let text = match ron::ser::to_string_pretty(self, default()) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Failed to serialize settings: {e}");
+                return;
+            }
+        };
+        if let Err(e) = std::fs::write(SETTINGS_PATH, text) {
+            warn!("Failed to write {SETTINGS_PATH}: {e}");
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FrameLimit {
+    /// Match the monitor refresh rate.
+    Auto,
+    Fps(u32),
+    Off,
+}
+
+impl From<FrameLimit> for Limiter {
+    fn from(value: FrameLimit) -> Self {
+        match value {
+            FrameLimit::Auto => Limiter::Auto,
+            FrameLimit::Fps(fps) => Limiter::from_framerate(fps as f64),
+            FrameLimit::Off => Limiter::Off,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlScheme {
+    /// The player follows the cursor.
+    Mouse,
+    /// The player is moved with the movement keys.
+    Keyboard,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl From<Binding> for UserInput {
+    fn from(value: Binding) -> Self {
+        match value {
+            Binding::Key(key) => key.into(),
+            Binding::Mouse(button) => button.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Controls {
+    pub scheme: ControlScheme,
+    pub attack: Binding,
+    pub dodge: Binding,
+    pub switch_shot: Binding,
+    pub move_up: KeyCode,
+    pub move_down: KeyCode,
+    pub move_left: KeyCode,
+    pub move_right: KeyCode,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self {
+            scheme: ControlScheme::Mouse,
+            attack: Binding::Mouse(MouseButton::Left),
+            dodge: Binding::Mouse(MouseButton::Right),
+            switch_shot: Binding::Mouse(MouseButton::Middle),
+            move_up: KeyCode::Up,
+            move_down: KeyCode::Down,
+            move_left: KeyCode::Left,
+            move_right: KeyCode::Right,
+        }
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut framepace: ResMut<FramepaceSettings>,
+) {
+    if let Ok(mut window) = window.get_single_mut() {
+        let scale = Some(settings.window_scale as f64);
+        if window.resolution.scale_factor_override() != scale {
+            window.resolution.set_scale_factor_override(scale);
+        }
+    }
+    *global_volume = GlobalVolume::new(settings.master_volume);
+    framepace.limiter = settings.frame_limit.into();
+}
+
+fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+enum SettingKind {
+    MasterVolume,
+    SfxVolume,
+    MusicVolume,
+    WindowScale,
+    FrameLimit,
+    Lives,
+    ControlScheme,
+    Attack,
+    Dodge,
+    SwitchShot,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+}
+
+impl SettingKind {
+    const ALL: [SettingKind; 14] = [
+        SettingKind::MasterVolume,
+        SettingKind::SfxVolume,
+        SettingKind::MusicVolume,
+        SettingKind::WindowScale,
+        SettingKind::FrameLimit,
+        SettingKind::Lives,
+        SettingKind::ControlScheme,
+        SettingKind::Attack,
+        SettingKind::Dodge,
+        SettingKind::SwitchShot,
+        SettingKind::MoveUp,
+        SettingKind::MoveDown,
+        SettingKind::MoveLeft,
+        SettingKind::MoveRight,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SettingKind::MasterVolume => "Master volume",
+            SettingKind::SfxVolume => "SFX volume",
+            SettingKind::MusicVolume => "Music volume",
+            SettingKind::WindowScale => "Window scale",
+            SettingKind::FrameLimit => "Frame limit",
+            SettingKind::Lives => "Lives",
+            SettingKind::ControlScheme => "Controls",
+            SettingKind::Attack => "Attack",
+            SettingKind::Dodge => "Dodge",
+            SettingKind::SwitchShot => "Switch shot",
+            SettingKind::MoveUp => "Move up",
+            SettingKind::MoveDown => "Move down",
+            SettingKind::MoveLeft => "Move left",
+            SettingKind::MoveRight => "Move right",
+        }
+    }
+
+    fn is_binding(self) -> bool {
+        matches!(
+            self,
+            SettingKind::Attack
+                | SettingKind::Dodge
+                | SettingKind::SwitchShot
+                | SettingKind::MoveUp
+                | SettingKind::MoveDown
+                | SettingKind::MoveLeft
+                | SettingKind::MoveRight
+        )
+    }
+
+    fn value_text(self, settings: &Settings) -> String {
+        let binding = |b: &Binding| match b {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+        };
+        let controls = &settings.controls;
+        match self {
+            SettingKind::MasterVolume => format!("{:.0}%", settings.master_volume * 100.),
+            SettingKind::SfxVolume => format!("{:.0}%", settings.sfx_volume * 100.),
+            SettingKind::MusicVolume => format!("{:.0}%", settings.music_volume * 100.),
+            SettingKind::WindowScale => format!("x{}", settings.window_scale),
+            SettingKind::FrameLimit => match settings.frame_limit {
+                FrameLimit::Auto => "Auto".to_string(),
+                FrameLimit::Fps(fps) => format!("{fps} FPS"),
+                FrameLimit::Off => "Off".to_string(),
+            },
+            SettingKind::Lives => settings.lives.to_string(),
+            SettingKind::ControlScheme => format!("{:?}", controls.scheme),
+            SettingKind::Attack => binding(&controls.attack),
+            SettingKind::Dodge => binding(&controls.dodge),
+            SettingKind::SwitchShot => binding(&controls.switch_shot),
+            SettingKind::MoveUp => format!("{:?}", controls.move_up),
+            SettingKind::MoveDown => format!("{:?}", controls.move_down),
+            SettingKind::MoveLeft => format!("{:?}", controls.move_left),
+            SettingKind::MoveRight => format!("{:?}", controls.move_right),
+        }
+    }
+
+    /// Moves the setting to the previous (`dir < 0`) or next (`dir > 0`) value.
+    fn step(self, settings: &mut Settings, dir: i32) {
+        fn step_volume(volume: &mut f32, dir: i32) {
+            *volume = (((*volume * 10.).round() + dir as f32) / 10.).clamp(0., 1.);
+        }
+
+        fn step_in<T: PartialEq + Copy>(values: &[T], value: &mut T, dir: i32) {
+            let i = values.iter().position(|v| v == value).unwrap_or(0) as i32;
+            *value = values[(i + dir).clamp(0, values.len() as i32 - 1) as usize];
+        }
+
+        match self {
+            SettingKind::MasterVolume => step_volume(&mut settings.master_volume, dir),
+            SettingKind::SfxVolume => step_volume(&mut settings.sfx_volume, dir),
+            SettingKind::MusicVolume => step_volume(&mut settings.music_volume, dir),
+            SettingKind::WindowScale => step_in(&WINDOW_SCALES, &mut settings.window_scale, dir),
+            SettingKind::FrameLimit => step_in(&FRAME_LIMITS, &mut settings.frame_limit, dir),
+            SettingKind::Lives => step_in(&LIVES, &mut settings.lives, dir),
+            SettingKind::ControlScheme => {
+                settings.controls.scheme = match settings.controls.scheme {
+                    ControlScheme::Mouse => ControlScheme::Keyboard,
+                    ControlScheme::Keyboard => ControlScheme::Mouse,
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Component)]
+struct SettingsMenu;
+
+#[derive(Component)]
+enum SettingsButton {
+    Step(SettingKind, i32),
+    Rebind(SettingKind),
+    Back,
+}
+
+#[derive(Component)]
+struct SettingValue(SettingKind);
+
+/// The binding currently waiting for an input.
+#[derive(Resource)]
+struct Rebinding(SettingKind);
+
+fn spawn_settings_menu(mut commands: Commands, settings: Res<Settings>) {
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::WHITE,
+        ..default()
+    };
+    let button_bundle = |width: f32| ButtonBundle {
+        style: Style {
+            width: Val::Px(width),
+            height: Val::Px(30.),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: BUTTON_COLOR.into(),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Name::new("Settings menu"),
+            SettingsMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(6.),
+                    ..default()
+                },
+                background_color: Color::BLACK.with_a(0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn(TextBundle::from_section(
+                "SETTINGS",
+                TextStyle {
+                    font_size: 40.,
+                    color: Color::CYAN,
+                    ..default()
+                },
+            ));
+
+            for kind in SettingKind::ALL {
+                c.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(480.),
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(8.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|c| {
+                    c.spawn(
+                        TextBundle::from_section(kind.label(), text_style(20.)).with_style(Style {
+                            flex_grow: 1.,
+                            ..default()
+                        }),
+                    );
+
+                    let value =
+                        TextBundle::from_section(kind.value_text(&settings), text_style(20.));
+                    if kind.is_binding() {
+                        c.spawn((SettingsButton::Rebind(kind), button_bundle(216.)))
+                            .with_children(|c| {
+                                c.spawn((SettingValue(kind), value));
+                            });
+                    } else {
+                        c.spawn((SettingsButton::Step(kind, -1), button_bundle(30.)))
+                            .with_children(|c| {
+                                c.spawn(TextBundle::from_section("<", text_style(20.)));
+                            });
+                        c.spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(140.),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|c| {
+                            c.spawn((SettingValue(kind), value));
+                        });
+                        c.spawn((SettingsButton::Step(kind, 1), button_bundle(30.)))
+                            .with_children(|c| {
+                                c.spawn(TextBundle::from_section(">", text_style(20.)));
+                            });
+                    }
+                });
+            }
+
+            c.spawn((SettingsButton::Back, button_bundle(200.)))
+                .with_children(|c| {
+                    c.spawn(TextBundle::from_section("Back", text_style(24.)));
+                });
+        });
+}
+
+fn despawn_settings_menu(mut commands: Commands, menu: Query<Entity, With<SettingsMenu>>) {
+    commands.remove_resource::<Rebinding>();
+    for e in &menu {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn settings_menu_buttons(
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &SettingsButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut settings: ResMut<Settings>,
+    mut menu_state: ResMut<NextState<SettingsMenuState>>,
+    keys: Res<Input<KeyCode>>,
+    rebinding: Option<Res<Rebinding>>,
+    mut sounds: EventWriter<SoundEvent>,
+) {
+    if rebinding.is_none() && keys.just_pressed(KeyCode::Escape) {
+        menu_state.set(SettingsMenuState::Closed);
+    }
+
+    for (interaction, button, mut color) in &mut buttons {
+        match interaction {
+            Interaction::Pressed => {
+                sounds.send(SoundEvent(Sound::MenuSelect));
+                match *button {
+                    SettingsButton::Step(kind, dir) => kind.step(&mut settings, dir),
+                    SettingsButton::Rebind(kind) => {
+                        commands.insert_resource(Rebinding(kind));
+                        // Refresh the texts to show the prompt.
+                        settings.set_changed();
+                    }
+                    SettingsButton::Back => menu_state.set(SettingsMenuState::Closed),
+                }
+            }
+            Interaction::Hovered => {
+                sounds.send(SoundEvent(Sound::MenuMove));
+                *color = BUTTON_HOVERED_COLOR.into();
+            }
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+fn rebind_system(
+    mut commands: Commands,
+    rebinding: Option<Res<Rebinding>>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(rebinding) = rebinding else {
+        return;
+    };
+    // Ignore the click that started rebinding.
+    if rebinding.is_added() {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.remove_resource::<Rebinding>();
+        settings.set_changed();
+        return;
+    }
+
+    let key = keys.get_just_pressed().next().copied();
+    let mouse_button = mouse_buttons.get_just_pressed().next().copied();
+    let binding = key.map(Binding::Key).or(mouse_button.map(Binding::Mouse));
+
+    let controls = &settings.controls;
+    let controls = match (rebinding.0, binding) {
+        (SettingKind::Attack, Some(attack)) => Controls {
+            attack,
+            ..controls.clone()
+        },
+        (SettingKind::Dodge, Some(dodge)) => Controls {
+            dodge,
+            ..controls.clone()
+        },
+        (SettingKind::SwitchShot, Some(switch_shot)) => Controls {
+            switch_shot,
+            ..controls.clone()
+        },
+        (SettingKind::MoveUp, Some(Binding::Key(move_up))) => Controls {
+            move_up,
+            ..controls.clone()
+        },
+        (SettingKind::MoveDown, Some(Binding::Key(move_down))) => Controls {
+            move_down,
+            ..controls.clone()
+        },
+        (SettingKind::MoveLeft, Some(Binding::Key(move_left))) => Controls {
+            move_left,
+            ..controls.clone()
+        },
+        (SettingKind::MoveRight, Some(Binding::Key(move_right))) => Controls {
+            move_right,
+            ..controls.clone()
+        },
+        _ => return,
+    };
+
+    settings.controls = controls;
+    commands.remove_resource::<Rebinding>();
+}
+
+fn update_settings_text(
+    settings: Res<Settings>,
+    rebinding: Option<Res<Rebinding>>,
+    mut texts: Query<(&mut Text, &SettingValue)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for (mut text, SettingValue(kind)) in &mut texts {
+        text.sections[0].value = match &rebinding {
+            Some(rebinding) if rebinding.0 == *kind => "Press a key...".to_string(),
+            _ => kind.value_text(&settings),
+        };
+    }
+}
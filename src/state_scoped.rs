@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::GameState;
+
+pub struct StateScopedPlugin;
+
+impl Plugin for StateScopedPlugin {
+    fn build(&self, app: &mut App) {
+        for state in [
+            GameState::Title,
+            GameState::Main,
+            GameState::GameOver,
+            GameState::Editor,
+        ] {
+            // The game over screen still shows the end of the run, so the entities of the run are
+            // despawned when leaving it instead.
+            let exited = if state == GameState::Main {
+                GameState::GameOver
+            } else {
+                state
+            };
+            app.add_systems(
+                OnExit(exited),
+                move |mut commands: Commands, q: Query<(Entity, &StateScoped)>| {
+                    for (id, scoped) in &q {
+                        if scoped.0 == state {
+                            commands.entity(id).despawn_recursive();
+                        }
+                    }
+                },
+            );
+        }
+    }
+}
+
+/// Despawns the entity when leaving the state it belongs to. Entities of
+/// [`GameState::Main`] stay over the game over screen and are despawned when leaving
+/// [`GameState::GameOver`] instead.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateScoped(pub GameState);
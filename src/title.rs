@@ -1,397 +1,618 @@
-use bevy::{prelude::*, sprite::Mesh2dHandle};
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+#[cfg(feature = "dev")]
 use bevy_debug_text_overlay::screen_print;
 
-use crate::GameState;
+use crate::{
+    audio::{Sound, SoundEvent},
+    difficulty::Difficulty,
+    drop_text::{DropText, Splash},
+    level::{StageStart, StartPosition},
+    score::HighScores,
+    settings::SettingsMenuState,
+    state_scoped::StateScoped,
+    GameState,
+};
+
+const BUTTON_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const BUTTON_HOVERED_COLOR: Color = Color::rgba(0.0, 0.5, 0.5, 0.6);
+
+/// Stages listed in Stage Select.
+const STAGES: [&str; 1] = ["Stage 1"];
+
+const PRACTICE_STARTS: [StartPosition; 6] = [
+    StartPosition::CameraY(0.),
+    StartPosition::CameraY(-200.),
+    StartPosition::CameraY(-400.),
+    StartPosition::CameraY(-600.),
+    StartPosition::CameraY(-800.),
+    StartPosition::Boss,
+];
+
+const PRACTICE_RADII: [Option<f32>; 5] = [None, Some(10.), Some(20.), Some(30.), Some(40.)];
 
 pub struct TitlePlugin;
 
 impl Plugin for TitlePlugin {
     fn build(&self, app: &mut App) {
         // FIXME
-        app.add_systems(OnEnter(GameState::Title), title_setup);
-        app.add_systems(Update, title_system.run_if(in_state(GameState::Title)));
-        app.add_systems(Update, splash_drops.run_if(in_state(GameState::Main)));
+        app.init_resource::<GameMode>()
+            .init_resource::<MenuSelection>()
+            .init_resource::<PracticeOptions>();
+        app.add_systems(OnEnter(GameState::Title), (title_setup, spawn_title_ui));
+        app.add_systems(OnExit(GameState::Title), |mut commands: Commands| {
+            commands.remove_resource::<TitlePage>();
+        });
+        app.add_systems(
+            Update,
+            (title_menu, difficulty_button)
+                .run_if(in_state(GameState::Title).and_then(in_state(SettingsMenuState::Closed))),
+        );
+        app.add_systems(
+            Update,
+            (spawn_menu_page, update_menu_buttons, update_title_ui)
+                .chain()
+                .run_if(in_state(GameState::Title)),
+        );
     }
 }
 
-#[derive(Component)]
-struct TitleDrop;
-
-// fn title_system(
-//     mut commands: Commands,
-//     buttons: Res<Input<MouseButton>>,
-//     mut title_res: ResMut<TitleRes>,
-//     mut meshes: ResMut<Assets<Mesh>>,
-//     mut color_materials: ResMut<Assets<ColorMaterial>>,
-//     mouse_pos: Option<Res<MouseWorldPos>>,
-//     mut timer: Local<Option<Timer>>,
-//     time: Res<Time>,
-//     transform: Query<&Transform>,
-// ) {
-//     let timer = timer
-//         .get_or_insert_with(|| Timer::from_seconds(0.1, TimerMode::Repeating))
-//         .tick(time.delta());
-
-//     debug!("title_system {timer:?}");
-//     if let Some(mouse_pos) = mouse_pos {
-//         if buttons.pressed(MouseButton::Right) && timer.just_finished() {
-//             debug!("pressed {:?}", mouse_pos.0);
-//             let id = commands
-//                 .spawn(ColorMesh2dBundle {
-//                     mesh: Mesh2dHandle(
-//                         meshes.add(
-//                             shape::Circle {
-//                                 radius: 6.0,
-//                                 vertices: 6,
-//                             }
-//                             .into(),
-//                         ),
-//                     ),
-//                     material: color_materials.add(ColorMaterial::from(Color::CYAN)),
-//                     transform: Transform::from_translation(mouse_pos.0.extend(0.)),
-//                     ..default()
-//                 })
-//                 .id();
-//             title_res.drops.push(id);
-//         }
-//     }
-//     if buttons.just_pressed(MouseButton::Left) {
-//         let pos = title_res
-//             .drops
-//             .iter()
-//             .map(|&e| transform.get(e).unwrap())
-//             .collect::<Vec<_>>();
-//         let mut file = File::create("title.txt").unwrap();
-//         for t in pos.into_iter() {
-//             writeln!(file, "[{}, {}],", t.translation.x, t.translation.y).unwrap();
-//         }
-
-//         commands.remove_resource::<TitleRes>();
-//     }
-// }
+/// How the current run was started from the title menu.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Normal,
+    /// Runs that don't count for the high scores.
+    Practice,
+}
 
 #[derive(Component)]
 pub struct TitleRoot;
 
-fn title_setup(
+fn title_setup(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Title root"),
+        TitleRoot,
+        DropText::new("Raintities"),
+        SpatialBundle::from_transform(Transform::from_xyz(0., 90., 1.)),
+    ));
+}
+
+#[derive(Component)]
+struct TitleUi;
+
+#[derive(Component)]
+struct DifficultyButton;
+
+#[derive(Component)]
+struct HighScoreText;
+
+/// Column the buttons of the current [`TitlePage`] are spawned in.
+#[derive(Component)]
+struct TitleMenuList;
+
+/// Page of the title menu shown.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+enum TitlePage {
+    #[default]
+    Main,
+    StageSelect,
+    HighScores,
+    Practice,
+}
+
+/// Indices into [`PRACTICE_STARTS`] and [`PRACTICE_RADII`].
+#[derive(Resource, Default)]
+struct PracticeOptions {
+    start: usize,
+    radius: usize,
+}
+
+/// Index of the highlighted button on the current page.
+#[derive(Resource, Default)]
+struct MenuSelection(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuAction {
+    Start,
+    Practice,
+    StageSelect,
+    Replays,
+    HighScores,
+    Editor,
+    Settings,
+    Quit,
+    Stage(usize),
+    PracticeStart,
+    PracticeRadius,
+    StartPractice,
+    Back,
+}
+
+#[derive(Component)]
+struct MenuButton {
+    index: usize,
+    action: MenuAction,
+    enabled: bool,
+}
+
+fn spawn_title_ui(mut commands: Commands) {
+    commands.insert_resource(TitlePage::Main);
+
+    let text_style = TextStyle {
+        font_size: 20.,
+        color: Color::WHITE,
+        ..default()
+    };
+
+    commands.spawn((
+        Name::new("Title menu"),
+        TitleUi,
+        StateScoped(GameState::Title),
+        TitleMenuList,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(32.),
+                top: Val::Px(0.),
+                bottom: Val::Px(0.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    commands
+        .spawn((
+            Name::new("Title UI"),
+            TitleUi,
+            StateScoped(GameState::Title),
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(16.),
+                    right: Val::Px(16.),
+                    bottom: Val::Px(16.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn((
+                DifficultyButton,
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(160.),
+                        height: Val::Px(32.),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: BUTTON_COLOR.into(),
+                    ..default()
+                },
+            ))
+            .with_children(|c| {
+                // Filled in by update_title_ui
+                c.spawn(TextBundle::from_section("", text_style.clone()));
+            });
+            c.spawn((HighScoreText, TextBundle::from_section("", text_style)));
+        });
+}
+
+// Replaces the buttons when the page changes.
+fn spawn_menu_page(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    page: Res<TitlePage>,
+    list: Query<Entity, With<TitleMenuList>>,
+    new_list: Query<(), Added<TitleMenuList>>,
+    high_scores: Res<HighScores>,
+    practice: Res<PracticeOptions>,
+    mut selection: ResMut<MenuSelection>,
 ) {
-    let drop_pos = vec![
-        [-244., 123.99999],
-        [-244., 110.],
-        [-242.99998, 100.000015],
-        [-242.99998, 90.999985],
-        [-242.99998, 77.],
-        [-242.99998, 66.00001],
-        [-244., 57.000015],
-        [-244., 48.99999],
-        [-244., 46.999992],
-        [-236.99998, 123.99999],
-        [-233., 123.99999],
-        [-228., 124.999985],
-        [-219., 124.999985],
-        [-211.00002, 123.99999],
-        [-208., 122.99999],
-        [-205., 116.99999],
-        [-203., 111.],
-        [-202., 105.00001],
-        [-203., 94.000015],
-        [-209.99998, 89.999985],
-        [-215.99998, 86.999985],
-        [-225., 86.999985],
-        [-232.00002, 85.99999],
-        [-236.00002, 85.99999],
-        [-217.99998, 83.99999],
-        [-215.00002, 77.],
-        [-209.99998, 69.00001],
-        [-204., 60.000015],
-        [-199., 49.99999],
-        [-199., 48.99999],
-        [-170., 104.00001],
-        [-156., 103.00001],
-        [-149., 103.00001],
-        [-140.00002, 102.00001],
-        [-133.99998, 98.000015],
-        [-132., 85.99999],
-        [-132., 71.],
-        [-132., 60.000015],
-        [-132., 53.999985],
-        [-132., 46.999992],
-        [-132., 49.99999],
-        [-141.00002, 79.99999],
-        [-148., 80.99999],
-        [-157., 80.99999],
-        [-164.00002, 77.],
-        [-168., 73.],
-        [-169., 67.00001],
-        [-170., 57.000015],
-        [-164.00002, 49.99999],
-        [-150., 47.99999],
-        [-139.00002, 53.999985],
-        [-132., 59.000015],
-        [-105.99999, 102.00001],
-        [-106.99999, 90.999985],
-        [-106.99999, 78.],
-        [-106.99999, 65.00001],
-        [-107.99999, 52.999985],
-        [-107.99999, 50.99999],
-        [-102., 125.999985],
-        [-104., 125.999985],
-        [-105.99999, 125.999985],
-        [-74., 105.00001],
-        [-74., 98.000015],
-        [-74., 83.99999],
-        [-74., 73.],
-        [-74., 62.00001],
-        [-74., 52.999985],
-        [-74., 49.99999],
-        [-74., 49.99999],
-        [-74., 55.99998],
-        [-68.00001, 95.000015],
-        [-64.00001, 98.000015],
-        [-56.999992, 101.00001],
-        [-52.999996, 102.00001],
-        [-47.000004, 102.00001],
-        [-42.000008, 102.00001],
-        [-36.99999, 100.000015],
-        [-31.999992, 96.000015],
-        [-28.999996, 88.999985],
-        [-27.999996, 81.99999],
-        [-26.999998, 72.],
-        [-26.999998, 64.00001],
-        [-26.999998, 55.99998],
-        [-26.999998, 52.999985],
-        [-8.999991, 106.00001],
-        [-6.9999933, 106.00001],
-        [5.000019, 105.00001],
-        [9.99999, 105.00001],
-        [15.00001, 105.00001],
-        [15.00001, 105.00001],
-        [5.000019, 116.],
-        [5.000019, 107.00001],
-        [5.000019, 99.000015],
-        [3.9999962, 91.999985],
-        [3.000021, 81.99999],
-        [3.000021, 75.],
-        [3.9999962, 68.00001],
-        [5.000019, 61.000015],
-        [7.9999924, 52.999985],
-        [13.999987, 48.99999],
-        [19.99998, 48.99999],
-        [21.999979, 48.99999],
-        [44.99998, 97.000015],
-        [44.99998, 79.99999],
-        [44.99998, 63.00001],
-        [42.999985, 52.999985],
-        [42.999985, 50.99999],
-        [42.999985, 48.99999],
-        [42.000008, 132.00002],
-        [42.000008, 128.99998],
-        [42.000008, 127.999985],
-        [42.000008, 127.999985],
-        [59.999992, 108.00001],
-        [75., 106.00001],
-        [84.99999, 105.00001],
-        [90.999985, 105.00001],
-        [90.999985, 105.00001],
-        [77., 120.99999],
-        [77., 105.00001],
-        [77., 91.999985],
-        [77., 81.99999],
-        [77., 74.],
-        [78.00002, 64.00001],
-        [80.000015, 59.000015],
-        [84.000015, 54.999985],
-        [92.00001, 53.999985],
-        [100., 53.999985],
-        // [117.00001, 109.],
-        [119.00001, 87.999985],
-        [119.00001, 75.00001],
-        [119.00001, 65.00001],
-        [119.00001, 49.99999],
-        [119.00001, 44.999992],
-        [117.999985, 44.999992],
-        [115.999985, 124.999985],
-        [115.999985, 127.999985],
-        [115.999985, 127.999985],
-        [150., 82.99999],
-        [159.00002, 81.99999],
-        [178.00002, 79.],
-        [184.99998, 79.],
-        [186.99998, 79.],
-        [188.00002, 86.999985],
-        [184.99998, 97.000015],
-        [179., 103.00001],
-        [165.00002, 101.00001],
-        [158., 100.000015],
-        [151.00003, 96.000015],
-        [148.99997, 85.99999],
-        [148.99997, 70.00001],
-        [154., 62.00001],
-        [161.00002, 53.999985],
-        [171., 52.999985],
-        [179., 52.999985],
-        [186.99998, 52.999985],
-        [244.99998, 105.00001],
-        [238.00002, 107.00001],
-        [225., 109.],
-        [217., 104.00001],
-        [211.99998, 95.000015],
-        [213.00002, 82.99999],
-        [223.99997, 77.],
-        [234.00002, 76.],
-        [242.99998, 73.],
-        [246.99998, 71.],
-        [248., 65.00001],
-        [248., 58.000015],
-        [244.99998, 52.999985],
-        [236.00002, 49.99999],
-        [225., 49.99999],
-        [221., 50.99999],
-        [213.00002, 53.999985],
-        [211.99998, 55.99998],
-        [-59.999992, -66.00001],
-        [-48., -67.00001],
-        [-35.99999, -69.00001],
-        [-27.999996, -71.],
-        [-17.000008, -75.],
-        [-10.9999895, -81.99999],
-        [-8.999991, -92.],
-        [-7.9999924, -98.99999],
-        [-7.9999924, -110.],
-        [-7.9999924, -115.],
-        [-7.9999924, -118.99999],
-        [-6.9999933, -123.00001],
-        [-5.9999943, -125.00001],
-        [-17.000008, -131.],
-        [-28.999996, -132.],
-        [-31.999992, -132.],
-        [-36.99999, -134.],
-        [-40.000008, -137.],
-        [-46.000004, -141.00002],
-        [-50., -147.],
-        [-52., -152.],
-        [-54.999996, -158.99998],
-        [-56.999992, -169.],
-        [-59.999992, -184.],
-        [-59.999992, -193.],
-        [-59.999992, -206.],
-        [-59.999992, -222.],
-        [-57.999992, -233.],
-        [-57.999992, -242.],
-        [-55.999992, -251.00002],
-        [-51., -257.],
-        [-38.00001, -264.],
-        [-26., -269.],
-        [-10.9999895, -272.],
-        [1.0000229, -272.],
-        [7.9999924, -272.],
-        [17.000008, -269.],
-        [25., -258.],
-        [28.000021, -247.],
-        [32.000015, -235.99998],
-        [36.000015, -225.],
-        [36.000015, -216.00002],
-        [36.000015, -206.],
-        [36.000015, -194.],
-        [36.000015, -183.],
-        [36.000015, -172.],
-        [34.999992, -162.99998],
-        [32.999992, -155.],
-        [30.00002, -149.],
-        [23.999977, -144.],
-        [15.999985, -141.00002],
-        [11.999989, -139.99998],
-        [3.000021, -138.],
-        [-4.999995, -138.],
-        [-8.999991, -137.],
-        [-57.999992, -202.],
-        [-57.999992, -202.],
-        [-43.000008, -203.],
-        [-30.999994, -204.],
-        [-22.000004, -205.],
-        [-22.000004, -205.],
-        [-20.000004, -205.],
-        [-17.000008, -139.99998],
-        [-17.000008, -143.],
-        [-17.000008, -152.],
-        [-17.000008, -163.99998],
-        [-17.000008, -176.00002],
-        [-17.000008, -185.],
-        [-17.000008, -192.],
-        [-16.000008, -194.],
-        [-28.999996, -143.],
-        [-39.00001, -147.],
-        [-46.000004, -157.],
-        [-52., -172.],
-        [-52., -175.00002],
-        [-41.000008, -167.],
-        [-26.999998, -148.],
-        [-26., -148.],
-        [-29.999996, -158.99998],
-        [-41.000008, -181.],
-        [-44.000008, -187.],
-        [-45.000004, -190.],
-        [-45.000004, -190.],
-        [-29.999996, -186.],
-        [-22.000004, -174.00002],
-        [-19.000006, -172.],
-        [-22.000004, -187.],
-        [-24., -193.],
-        [-25., -195.99998],
-        [-28.999996, -196.99998],
-        [-33.999992, -195.99998],
-    ];
-    let mesh = Mesh2dHandle(
-        meshes.add(
-            shape::Circle {
-                radius: 6.0,
-                vertices: 6,
+    if !page.is_changed() && !practice.is_changed() && new_list.is_empty() {
+        return;
+    }
+    let Ok(list) = list.get_single() else {
+        return;
+    };
+
+    let entries: Vec<(String, MenuAction, bool)> = match *page {
+        TitlePage::Main => vec![
+            ("Start".into(), MenuAction::Start, true),
+            ("Practice".into(), MenuAction::Practice, true),
+            ("Stage Select".into(), MenuAction::StageSelect, true),
+            // Nothing records replays yet.
+            ("Replays".into(), MenuAction::Replays, false),
+            ("High Scores".into(), MenuAction::HighScores, true),
+            ("Level Editor".into(), MenuAction::Editor, true),
+            ("Settings".into(), MenuAction::Settings, true),
+            ("Quit".into(), MenuAction::Quit, true),
+        ],
+        TitlePage::StageSelect => STAGES
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| (name.into(), MenuAction::Stage(i), true))
+            .chain([("Back".into(), MenuAction::Back, true)])
+            .collect(),
+        TitlePage::HighScores => vec![("Back".into(), MenuAction::Back, true)],
+        TitlePage::Practice => {
+            let start = match PRACTICE_STARTS[practice.start] {
+                StartPosition::CameraY(0.) => "Top".to_string(),
+                StartPosition::CameraY(y) => format!("y {y}"),
+                StartPosition::Boss => "Boss".to_string(),
+            };
+            let radius = match PRACTICE_RADII[practice.radius] {
+                Some(radius) => radius.to_string(),
+                None => "Default".to_string(),
+            };
+            vec![
+                (format!("< {start} >"), MenuAction::PracticeStart, true),
+                (
+                    format!("< Radius {radius} >"),
+                    MenuAction::PracticeRadius,
+                    true,
+                ),
+                ("Start".into(), MenuAction::StartPractice, true),
+                ("Back".into(), MenuAction::Back, true),
+            ]
+        }
+    };
+    // Changing a practice option keeps its button selected.
+    if page.is_changed() {
+        selection.0 = 0;
+    }
+
+    let text_style = |enabled: bool| TextStyle {
+        font_size: 24.,
+        color: if enabled { Color::WHITE } else { Color::GRAY },
+        ..default()
+    };
+
+    let mut list = commands.entity(list);
+    list.despawn_descendants();
+    list.with_children(|c| {
+        if *page == TitlePage::HighScores {
+            for difficulty in Difficulty::ALL {
+                c.spawn(TextBundle::from_section(
+                    format!(
+                        "{:<8} {:>8}",
+                        difficulty.name(),
+                        high_scores.get(difficulty)
+                    ),
+                    text_style(true),
+                ));
             }
-            .into(),
-        ),
-    );
-    let material = color_materials.add(ColorMaterial::from(Color::CYAN));
+        }
 
-    let root = commands.spawn((
-        Name::new("Title root"),
-        TitleRoot,
-        SpatialBundle::default(),
-    )).id();
+        for (index, (label, action, enabled)) in entries.into_iter().enumerate() {
+            c.spawn((
+                MenuButton {
+                    index,
+                    action,
+                    enabled,
+                },
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(200.),
+                        height: Val::Px(40.),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: BUTTON_COLOR.into(),
+                    ..default()
+                },
+            ))
+            .with_children(|c| {
+                c.spawn(TextBundle::from_section(label, text_style(enabled)));
+            });
+        }
+    });
+}
+
+/// Menu navigation from the keyboard and all connected gamepads.
+#[derive(SystemParam)]
+struct MenuInput<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    /// Stick direction of the last frame, so that holding the stick moves once.
+    stick: Local<'s, IVec2>,
+}
+
+#[derive(Default)]
+struct MenuCommands {
+    /// Positive moves the selection down.
+    vertical: i32,
+    horizontal: i32,
+    confirm: bool,
+    back: bool,
+}
+
+impl MenuInput<'_, '_> {
+    fn read(&mut self) -> MenuCommands {
+        let mut commands = MenuCommands::default();
+        if self.keys.any_just_pressed([KeyCode::Up, KeyCode::W]) {
+            commands.vertical -= 1;
+        }
+        if self.keys.any_just_pressed([KeyCode::Down, KeyCode::S]) {
+            commands.vertical += 1;
+        }
+        if self.keys.any_just_pressed([KeyCode::Left, KeyCode::A]) {
+            commands.horizontal -= 1;
+        }
+        if self.keys.any_just_pressed([KeyCode::Right, KeyCode::D]) {
+            commands.horizontal += 1;
+        }
+        commands.confirm |= self
+            .keys
+            .any_just_pressed([KeyCode::Return, KeyCode::Space]);
+        commands.back |= self.keys.just_pressed(KeyCode::Escape);
+
+        let mut stick = Vec2::ZERO;
+        for gamepad in self.gamepads.iter() {
+            let pressed = |button_type| {
+                self.buttons
+                    .just_pressed(GamepadButton::new(gamepad, button_type))
+            };
+            if pressed(GamepadButtonType::DPadUp) {
+                commands.vertical -= 1;
+            }
+            if pressed(GamepadButtonType::DPadDown) {
+                commands.vertical += 1;
+            }
+            if pressed(GamepadButtonType::DPadLeft) {
+                commands.horizontal -= 1;
+            }
+            if pressed(GamepadButtonType::DPadRight) {
+                commands.horizontal += 1;
+            }
+            commands.confirm |= pressed(GamepadButtonType::South);
+            commands.back |= pressed(GamepadButtonType::East);
+
+            let axis = |axis_type| {
+                self.axes
+                    .get(GamepadAxis::new(gamepad, axis_type))
+                    .unwrap_or(0.)
+            };
+            let value = Vec2::new(
+                axis(GamepadAxisType::LeftStickX),
+                axis(GamepadAxisType::LeftStickY),
+            );
+            if value.length() > stick.length() {
+                stick = value;
+            }
+        }
+
+        let direction = if stick.length() < 0.5 {
+            IVec2::ZERO
+        } else if stick.y.abs() > stick.x.abs() {
+            IVec2::new(0, -stick.y.signum() as i32)
+        } else {
+            IVec2::new(stick.x.signum() as i32, 0)
+        };
+        if direction != *self.stick {
+            commands.vertical += direction.y;
+            commands.horizontal += direction.x;
+        }
+        *self.stick = direction;
 
-    for pos in drop_pos {
         commands
-            .spawn(ColorMesh2dBundle {
-                mesh: mesh.clone(),
-                material: material.clone(),
-                transform: Transform::from_translation(Vec2::from(pos).extend(1.)),
-                ..default()
-            })
-            .insert(TitleDrop)
-            .set_parent(root);
     }
 }
 
-fn title_system(mut state: ResMut<NextState<GameState>>, buttons: Res<Input<MouseButton>>) {
-    if buttons.just_pressed(MouseButton::Left) {
-        // screen_print!("Start game");
-        state.set(GameState::Main);
+#[allow(clippy::too_many_arguments)]
+fn title_menu(
+    mut commands: Commands,
+    mut input: MenuInput,
+    interactions: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    buttons: Query<&MenuButton>,
+    mut selection: ResMut<MenuSelection>,
+    mut page: ResMut<TitlePage>,
+    mut difficulty: ResMut<Difficulty>,
+    mut practice: ResMut<PracticeOptions>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut settings_state: ResMut<NextState<SettingsMenuState>>,
+    mut exit: EventWriter<AppExit>,
+    mut sounds: EventWriter<SoundEvent>,
+    title_root: Query<Entity, With<TitleRoot>>,
+) {
+    let input = input.read();
+    let count = buttons.iter().count();
+    if count == 0 {
+        return;
+    }
+    if input.vertical != 0 || input.horizontal != 0 {
+        sounds.send(SoundEvent(Sound::MenuMove));
+    }
+
+    // Skip disabled buttons when moving the selection.
+    if input.vertical != 0 {
+        let step = input.vertical.signum();
+        let mut index = selection.0 as i32;
+        for _ in 0..count {
+            index = (index + step).rem_euclid(count as i32);
+            if buttons
+                .iter()
+                .any(|b| b.index == index as usize && b.enabled)
+            {
+                break;
+            }
+        }
+        selection.0 = index as usize;
+    }
+    let selected = buttons
+        .iter()
+        .find(|b| b.index == selection.0)
+        .map(|b| b.action);
+    if input.horizontal != 0 {
+        let step = input.horizontal.signum();
+        match (*page, selected) {
+            (TitlePage::Main, _) => {
+                *difficulty = if step > 0 {
+                    difficulty.next()
+                } else {
+                    difficulty.prev()
+                };
+            }
+            (_, Some(MenuAction::PracticeStart)) => {
+                practice.start = cycle(practice.start, step, PRACTICE_STARTS.len());
+            }
+            (_, Some(MenuAction::PracticeRadius)) => {
+                practice.radius = cycle(practice.radius, step, PRACTICE_RADII.len());
+            }
+            _ => {}
+        }
+    }
+
+    let mut action = None;
+    for (interaction, button) in &interactions {
+        match interaction {
+            Interaction::Hovered => {
+                selection.0 = button.index;
+                sounds.send(SoundEvent(Sound::MenuMove));
+            }
+            Interaction::Pressed if button.enabled => action = Some(button.action),
+            _ => {}
+        }
+    }
+    if input.confirm {
+        action = buttons
+            .iter()
+            .find(|b| b.index == selection.0 && b.enabled)
+            .map(|b| b.action);
+    }
+    if input.back && *page != TitlePage::Main {
+        action = Some(MenuAction::Back);
+    }
+
+    let Some(action) = action else {
+        return;
+    };
+    sounds.send(SoundEvent(Sound::MenuSelect));
+    let (mode, splash) = match action {
+        MenuAction::Start => (GameMode::Normal, true),
+        MenuAction::StartPractice => {
+            commands.insert_resource(StageStart {
+                position: PRACTICE_STARTS[practice.start],
+                player_radius: PRACTICE_RADII[practice.radius],
+            });
+            (GameMode::Practice, false)
+        }
+        MenuAction::Stage(stage) => {
+            // There is only one level so far, every stage starts it.
+            debug!("Starting {}", STAGES[stage]);
+            (GameMode::Normal, false)
+        }
+        MenuAction::Practice => {
+            *page = TitlePage::Practice;
+            return;
+        }
+        MenuAction::PracticeStart => {
+            practice.start = cycle(practice.start, 1, PRACTICE_STARTS.len());
+            return;
+        }
+        MenuAction::PracticeRadius => {
+            practice.radius = cycle(practice.radius, 1, PRACTICE_RADII.len());
+            return;
+        }
+        MenuAction::StageSelect => {
+            *page = TitlePage::StageSelect;
+            return;
+        }
+        MenuAction::HighScores => {
+            *page = TitlePage::HighScores;
+            return;
+        }
+        MenuAction::Back => {
+            *page = TitlePage::Main;
+            return;
+        }
+        MenuAction::Editor => {
+            game_state.set(GameState::Editor);
+            for root in &title_root {
+                commands.entity(root).despawn_recursive();
+            }
+            return;
+        }
+        MenuAction::Settings => {
+            settings_state.set(SettingsMenuState::Open);
+            return;
+        }
+        MenuAction::Quit => {
+            exit.send(AppExit);
+            return;
+        }
+        MenuAction::Replays => return,
+    };
+
+    // screen_print!("Start game");
+    commands.insert_resource(mode);
+    game_state.set(GameState::Main);
+    // Only Start splashes the title drops away.
+    for root in &title_root {
+        if splash {
+            commands.entity(root).insert(Splash);
+        } else {
+            commands.entity(root).despawn_recursive();
+        }
     }
 }
 
-fn splash_drops(
-    mut commands: Commands,
-    mut drops: Query<(Entity, &mut Transform), With<TitleDrop>>,
-    time: Res<Time>,
+fn cycle(index: usize, step: i32, len: usize) -> usize {
+    (index as i32 + step).rem_euclid(len as i32) as usize
+}
+
+fn update_menu_buttons(
+    selection: Res<MenuSelection>,
+    mut buttons: Query<(&MenuButton, &mut BackgroundColor)>,
+) {
+    for (button, mut color) in &mut buttons {
+        let new_color = if button.index == selection.0 {
+            BUTTON_HOVERED_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        if color.0 != new_color {
+            color.0 = new_color;
+        }
+    }
+}
+
+fn difficulty_button(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<DifficultyButton>)>,
+    mut difficulty: ResMut<Difficulty>,
 ) {
-    for (entity, mut tf) in &mut drops {
-        assert_ne!(tf.translation, Vec3::ZERO);
-        tf.translation = tf.translation + tf.translation.normalize() * time.delta_seconds() * 1000.;
+    if buttons.iter().any(|i| *i == Interaction::Pressed) {
+        *difficulty = difficulty.next();
+    }
+}
 
-        if tf.translation.length() > 1000. {
-            commands.entity(entity).despawn();
+fn update_title_ui(
+    difficulty: Res<Difficulty>,
+    high_scores: Res<HighScores>,
+    difficulty_button: Query<&Children, With<DifficultyButton>>,
+    mut high_score_text: Query<&mut Text, With<HighScoreText>>,
+    mut texts: Query<&mut Text, Without<HighScoreText>>,
+    new_ui: Query<(), Added<TitleUi>>,
+) {
+    if !difficulty.is_changed() && !high_scores.is_changed() && new_ui.is_empty() {
+        return;
+    }
+
+    for children in &difficulty_button {
+        if let Ok(mut text) = texts.get_mut(children[0]) {
+            text.sections[0].value = format!("< {} >", difficulty.name());
         }
     }
+    for mut text in &mut high_score_text {
+        text.sections[0].value = format!("Hi-Score: {}", high_scores.get(*difficulty));
+    }
 }